@@ -4,10 +4,16 @@
 
 pub mod pins;
 pub mod gpio_manager;
+pub mod monitor;
 
 // 重新导出常用类型
 pub use gpio_manager::GPIOManager;
 pub use pins::PinConfig;
+pub use monitor::MonitorConfig;
+
+use crate::service::alarm::AlarmConfig;
+use crate::utils::time::Timezone;
+use std::time::Duration;
 
 /// 默认引脚配置
 /// 
@@ -17,10 +23,53 @@ pub use pins::PinConfig;
 /// - SPI MOSI: GPIO0
 /// - SPI CS: GPIO18
 /// - SPI DC: GPIO12
+/// - 报警指示: 未启用（设置为 `Some(pin)` 以启用 LED/蜂鸣器）
+/// - 独立 I2C 屏幕: 未启用（`auto_detect` 探测 I2C 时复用 SPI SCK/MOSI 引脚）
 pub const PIN_CONFIG: PinConfig = PinConfig {
     temperature_sensor: 5,
     spi_sck: 2,
     spi_mosi: 0,
     spi_cs: 18,
     spi_dc: 12,
+    indicator: None,
+    i2c_sda: None,
+    i2c_scl: None,
+};
+
+/// 主循环节奏：每 5 秒读取一次传感器，期间每秒刷新一次屏幕时钟
+pub const MONITOR_CONFIG: MonitorConfig = MonitorConfig {
+    sample_interval: Duration::from_secs(5),
+    display_refresh: Duration::from_secs(1),
+};
+
+/// 屏幕显示时间使用的时区
+pub const TIMEZONE: Timezone = Timezone::china();
+
+/// 每次主循环增量校验的数据库记录数，设置为 0 禁用数据校验
+pub const SCRUB_RECORDS_PER_CYCLE: usize = 8;
+
+/// 温湿度报警阈值，读数回到阈值内侧 1.0 以外时解除报警
+pub const ALARM_CONFIG: AlarmConfig = AlarmConfig {
+    temp_high: Some(35.0),
+    temp_low: Some(5.0),
+    hum_high: Some(80.0),
+    hum_low: Some(20.0),
+    clear_margin: 1.0,
 };
+
+/// HTTP 查询服务的监听端口，设置为 `None` 不启动服务
+pub const HTTP_SERVER_PORT: Option<u16> = Some(80);
+
+/// MQTT broker 地址（如 `mqtt://192.168.1.10:1883`），设置为 `None` 不发布读数
+pub const MQTT_BROKER_URL: Option<&str> = None;
+
+/// 发布读数的 MQTT 主题
+pub const MQTT_TOPIC: &str = "esp-temperature-monitor/reading";
+
+/// 接收远程命令的 MQTT 主题，执行结果发布到 `<主题>/result`，设置为 `None` 不订阅命令
+///
+/// broker 上能发布到该主题的客户端都可以重启设备，启用前请配置 broker 的访问控制
+pub const MQTT_COMMAND_TOPIC: Option<&str> = None;
+
+/// 低功耗模式的采样间隔：每次读取、存储并显示后进入深度睡眠，设置为 `None` 时保持唤醒连续采样
+pub const DEEP_SLEEP_INTERVAL: Option<Duration> = None;
@@ -4,8 +4,10 @@
 
 use esp_idf_svc::hal::{
     gpio::AnyIOPin,
+    i2c::I2C0,
     peripheral::Peripheral,
     peripherals::Peripherals,
+    spi::SPI2,
 };
 use std::collections::HashSet;
 use thiserror::Error;
@@ -14,7 +16,7 @@ use crate::config::pins::PinConfig;
 
 /// GPIO 引脚配置
 /// 
-/// 包含所有已配置的 GPIO 引脚，所有权已从管理器转移
+/// 包含所有已配置的 GPIO 引脚及屏幕使用的 SPI2/I2C0 外设，所有权已从管理器转移
 pub struct GPIOConfig {
     /// 温度传感器引脚
     pub temperature_pin: AnyIOPin,
@@ -30,6 +32,21 @@ pub struct GPIOConfig {
     
     /// 屏幕数据/命令选择引脚
     pub spi_dc: AnyIOPin,
+    
+    /// 屏幕使用的 SPI2 外设
+    pub spi2: SPI2,
+    
+    /// 屏幕 I2C 探测及 I2C 模式使用的 I2C0 外设
+    pub i2c0: I2C0,
+    
+    /// 报警指示引脚（未配置时为 `None`）
+    pub indicator_pin: Option<AnyIOPin>,
+    
+    /// 独立 I2C 屏幕的 SDA 引脚（未配置时为 `None`）
+    pub i2c_sda: Option<AnyIOPin>,
+    
+    /// 独立 I2C 屏幕的 SCL 引脚（未配置时为 `None`）
+    pub i2c_scl: Option<AnyIOPin>,
 }
 
 /// GPIO 管理器错误类型
@@ -41,6 +58,9 @@ pub enum GPIOError {
     #[error("无效的引脚编号: {0}")]
     InvalidPin(u8),
     
+    #[error("外设 {0} 已被使用")]
+    PeripheralAlreadyUsed(&'static str),
+    
     #[error("GPIO 初始化失败: {0}")]
     GPIOInit(String),
 }
@@ -52,6 +72,8 @@ pub enum GPIOError {
 pub struct GPIOManager {
     peripherals: Peripherals,
     used_pins: HashSet<u8>,
+    spi2_used: bool,
+    i2c0_used: bool,
 }
 
 impl GPIOManager {
@@ -67,6 +89,8 @@ impl GPIOManager {
         Ok(Self {
             peripherals,
             used_pins: HashSet::new(),
+            spi2_used: false,
+            i2c0_used: false,
         })
     }
     
@@ -80,8 +104,10 @@ impl GPIOManager {
     /// * `Err(GPIOError)` - 配置失败
     /// 
     /// 返回一个元组，包含：
-    /// 1. 完整的 `Peripherals` 对象（用于访问 modem、SPI2 等其他外设）
-    /// 2. `GPIOConfig` 对象（包含所有已配置的 GPIO 引脚）
+    /// 1. 完整的 `Peripherals` 对象（用于访问 modem 等其他外设）
+    /// 2. `GPIOConfig` 对象（包含所有已配置的 GPIO 引脚和 SPI2/I2C0 外设）
+    /// 
+    /// SPI2 和 I2C0 已由管理器标记为已使用，不要再直接访问 `peripherals.spi2`/`peripherals.i2c0`。
     pub fn configure(mut self, config: &PinConfig) -> Result<(Peripherals, GPIOConfig), GPIOError> {
         // 验证配置
         crate::config::pins::validate_config(config)
@@ -93,6 +119,14 @@ impl GPIOManager {
         let spi_mosi = self.take_gpio(config.spi_mosi)?;
         let spi_cs = self.take_gpio(config.spi_cs)?;
         let spi_dc = self.take_gpio(config.spi_dc)?;
+        let spi2 = self.take_spi2()?;
+        let i2c0 = self.take_i2c0()?;
+        let indicator_pin = config
+            .indicator
+            .map(|pin| self.take_gpio(pin))
+            .transpose()?;
+        let i2c_sda = config.i2c_sda.map(|pin| self.take_gpio(pin)).transpose()?;
+        let i2c_scl = config.i2c_scl.map(|pin| self.take_gpio(pin)).transpose()?;
         
         Ok((
             self.peripherals,
@@ -102,10 +136,30 @@ impl GPIOManager {
                 spi_mosi,
                 spi_cs,
                 spi_dc,
+                spi2,
+                i2c0,
+                indicator_pin,
+                i2c_sda,
+                i2c_scl,
             }
         ))
     }
     
+    /// 预检查新的引脚配置，不获取任何引脚
+    /// 
+    /// 在运行时重新配置引脚前调用，检查白名单、重复引脚以及与当前已被占用引脚的冲突，
+    /// 避免错误配置在重新配置中途导致设备不可用。
+    /// 
+    /// # 参数
+    /// * `config` - 待应用的引脚配置
+    /// 
+    /// # 返回
+    /// * `Ok(())` - 配置可以安全应用
+    /// * `Err(GPIOError)` - 配置无效或与已占用引脚冲突
+    pub fn validate_against_current(&self, config: &PinConfig) -> Result<(), GPIOError> {
+        validate_against_used(config, &self.used_pins)
+    }
+    
     /// 安全地获取 GPIO 引脚
     /// 
     /// 使用 `clone_unchecked()` 创建引脚的克隆，同时跟踪已使用的引脚。
@@ -154,5 +208,116 @@ impl GPIOManager {
         self.used_pins.insert(pin_num);
         Ok(pin)
     }
+    
+    /// 安全地获取 SPI2 外设
+    /// 
+    /// 与 `take_gpio` 相同，使用 `clone_unchecked()` 并记录 SPI2 已被使用。
+    /// 
+    /// # 返回
+    /// * `Ok(SPI2)` - 外设获取成功
+    /// * `Err(GPIOError)` - SPI2 已被使用
+    pub fn take_spi2(&mut self) -> Result<SPI2, GPIOError> {
+        mark_peripheral_used(&mut self.spi2_used, "SPI2")?;
+        Ok(unsafe { self.peripherals.spi2.clone_unchecked() })
+    }
+    
+    /// 安全地获取 I2C0 外设
+    /// 
+    /// 与 `take_spi2` 相同，使用 `clone_unchecked()` 并记录 I2C0 已被使用。
+    /// 
+    /// # 返回
+    /// * `Ok(I2C0)` - 外设获取成功
+    /// * `Err(GPIOError)` - I2C0 已被使用
+    pub fn take_i2c0(&mut self) -> Result<I2C0, GPIOError> {
+        mark_peripheral_used(&mut self.i2c0_used, "I2C0")?;
+        Ok(unsafe { self.peripherals.i2c0.clone_unchecked() })
+    }
 }
 
+/// 检查配置本身是否有效，并且没有使用 `used_pins` 中已被占用的引脚
+fn validate_against_used(config: &PinConfig, used_pins: &HashSet<u8>) -> Result<(), GPIOError> {
+    crate::config::pins::validate_config(config)
+        .map_err(GPIOError::GPIOInit)?;
+    
+    match config.pins().into_iter().find(|pin| used_pins.contains(pin)) {
+        Some(pin) => Err(GPIOError::PinAlreadyUsed(pin)),
+        None => Ok(()),
+    }
+}
+
+/// 将外设标记为已使用，已被使用时返回 `PeripheralAlreadyUsed`，标记保持不变
+fn mark_peripheral_used(used: &mut bool, name: &'static str) -> Result<(), GPIOError> {
+    if *used {
+        return Err(GPIOError::PeripheralAlreadyUsed(name));
+    }
+    *used = true;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PIN_CONFIG;
+
+    #[test]
+    fn test_validate_conflicts_with_held_pins() {
+        let mut used_pins = HashSet::new();
+        assert!(validate_against_used(&PIN_CONFIG, &used_pins).is_ok());
+
+        // 传感器引脚已被占用
+        used_pins.insert(PIN_CONFIG.temperature_sensor);
+        assert!(matches!(
+            validate_against_used(&PIN_CONFIG, &used_pins),
+            Err(GPIOError::PinAlreadyUsed(pin)) if pin == PIN_CONFIG.temperature_sensor
+        ));
+
+        // 移到空闲引脚后通过
+        let moved = PinConfig { temperature_sensor: 4, ..PIN_CONFIG };
+        assert!(validate_against_used(&moved, &used_pins).is_ok());
+    }
+
+    #[test]
+    fn test_validate_invalid_entries() {
+        let used_pins = HashSet::new();
+
+        let invalid = PinConfig { spi_cs: 20, ..PIN_CONFIG };
+        assert!(matches!(validate_against_used(&invalid, &used_pins), Err(GPIOError::GPIOInit(_))));
+
+        let duplicate = PinConfig { indicator: Some(PIN_CONFIG.spi_dc), ..PIN_CONFIG };
+        assert!(matches!(validate_against_used(&duplicate, &used_pins), Err(GPIOError::GPIOInit(_))));
+    }
+
+    #[test]
+    fn test_validate_i2c_pins() {
+        let used_pins = HashSet::new();
+
+        let i2c = PinConfig { i2c_sda: Some(8), i2c_scl: Some(9), ..PIN_CONFIG };
+        assert!(validate_against_used(&i2c, &used_pins).is_ok());
+
+        // SDA 与 DHT 传感器引脚冲突
+        let sda_conflict = PinConfig { i2c_sda: Some(PIN_CONFIG.temperature_sensor), ..i2c };
+        assert!(matches!(validate_against_used(&sda_conflict, &used_pins), Err(GPIOError::GPIOInit(_))));
+
+        // 只配置了 SDA
+        let sda_only = PinConfig { i2c_scl: None, ..i2c };
+        assert!(matches!(validate_against_used(&sda_only, &used_pins), Err(GPIOError::GPIOInit(_))));
+
+        // 无效的 GPIO 编号
+        let invalid = PinConfig { i2c_scl: Some(20), ..i2c };
+        assert!(matches!(validate_against_used(&invalid, &used_pins), Err(GPIOError::GPIOInit(_))));
+    }
+
+    #[test]
+    fn test_peripheral_taken_once() {
+        let mut spi2_used = false;
+        assert!(mark_peripheral_used(&mut spi2_used, "SPI2").is_ok());
+        assert!(spi2_used);
+
+        // 第二次获取失败
+        assert!(matches!(
+            mark_peripheral_used(&mut spi2_used, "SPI2"),
+            Err(GPIOError::PeripheralAlreadyUsed("SPI2"))
+        ));
+        assert!(spi2_used);
+    }
+}
@@ -0,0 +1,61 @@
+//! 主循环配置
+//!
+//! 定义采样间隔和屏幕刷新间隔
+
+use std::time::Duration;
+
+/// 主循环的采样与显示节奏
+#[derive(Debug, Clone, Copy)]
+pub struct MonitorConfig {
+    /// 两次读取传感器的间隔，DHT22 要求至少间隔 2 秒
+    pub sample_interval: Duration,
+    /// 两次采样之间刷新屏幕时钟的间隔
+    pub display_refresh: Duration,
+}
+
+impl MonitorConfig {
+    /// 每个采样周期内刷新屏幕的次数，至少为 1；`display_refresh` 为 0 时每个周期刷新一次
+    pub fn refreshes_per_sample(&self) -> u32 {
+        let refresh = self.display_refresh.as_nanos();
+        if refresh == 0 {
+            return 1;
+        }
+        (self.sample_interval.as_nanos() / refresh).clamp(1, u32::MAX as u128) as u32
+    }
+
+    /// 每次刷新屏幕后的等待时间，保证一个采样周期的总时长等于 `sample_interval`
+    pub fn refresh_period(&self) -> Duration {
+        self.sample_interval / self.refreshes_per_sample()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(sample_ms: u64, refresh_ms: u64) -> MonitorConfig {
+        MonitorConfig {
+            sample_interval: Duration::from_millis(sample_ms),
+            display_refresh: Duration::from_millis(refresh_ms),
+        }
+    }
+
+    #[test]
+    fn test_refresh_schedule() {
+        let c = config(5000, 1000);
+        assert_eq!(c.refreshes_per_sample(), 5);
+        assert_eq!(c.refresh_period(), Duration::from_secs(1));
+
+        // 不能整除时均分采样间隔
+        let c = config(5000, 2000);
+        assert_eq!(c.refreshes_per_sample(), 2);
+        assert_eq!(c.refresh_period(), Duration::from_millis(2500));
+
+        // 刷新间隔大于采样间隔或为 0 时每个周期刷新一次
+        assert_eq!(config(5000, 10_000).refreshes_per_sample(), 1);
+        assert_eq!(config(5000, 10_000).refresh_period(), Duration::from_secs(5));
+        assert_eq!(config(5000, 0).refreshes_per_sample(), 1);
+        assert_eq!(config(5000, 0).refresh_period(), Duration::from_secs(5));
+        assert_eq!(config(0, 0).refreshes_per_sample(), 1);
+    }
+}
@@ -21,6 +21,34 @@ pub struct PinConfig {
     
     /// 屏幕数据/命令选择引脚 (GPIO12)
     pub spi_dc: u8,
+    
+    /// 报警指示灯/有源蜂鸣器引脚（可选，需支持推挽输出）
+    pub indicator: Option<u8>,
+    
+    /// 独立 I2C 屏幕的 SDA 引脚（可选，需与 `i2c_scl` 同时配置）
+    pub i2c_sda: Option<u8>,
+    
+    /// 独立 I2C 屏幕的 SCL 引脚（可选，需与 `i2c_sda` 同时配置）
+    pub i2c_scl: Option<u8>,
+}
+
+impl PinConfig {
+    /// 返回配置中使用的所有引脚编号，未配置的可选引脚不包含在内
+    pub fn pins(&self) -> Vec<u8> {
+        let mut pins = vec![
+            self.temperature_sensor,
+            self.spi_sck,
+            self.spi_mosi,
+            self.spi_cs,
+            self.spi_dc,
+        ];
+        
+        // 可选引脚仅在配置时参与检查
+        pins.extend(self.indicator);
+        pins.extend(self.i2c_sda);
+        pins.extend(self.i2c_scl);
+        pins
+    }
 }
 
 /// 验证引脚配置的有效性
@@ -32,13 +60,11 @@ pub struct PinConfig {
 /// * `Ok(())` - 配置有效
 /// * `Err(String)` - 配置无效，包含错误信息
 pub fn validate_config(config: &PinConfig) -> Result<(), String> {
-    let pins = [
-        config.temperature_sensor,
-        config.spi_sck,
-        config.spi_mosi,
-        config.spi_cs,
-        config.spi_dc,
-    ];
+    if config.i2c_sda.is_some() != config.i2c_scl.is_some() {
+        return Err("I2C 的 SDA 和 SCL 引脚需要同时配置".to_string());
+    }
+    
+    let pins = config.pins();
     
     // 检查是否有重复的引脚
     for i in 0..pins.len() {
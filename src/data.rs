@@ -1,2 +1,6 @@
+pub mod capture;
+pub mod display_pipeline;
 pub mod info_def;
+pub mod rtc_window;
+pub mod snapshot;
 pub mod time_db;
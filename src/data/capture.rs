@@ -0,0 +1,228 @@
+//! 高频突发采集
+//!
+//! 调试瞬态问题时，以亚秒级间隔在 RAM 环形缓冲区中短时间采集数据，
+//! 结束后序列化为紧凑的二进制格式导出，不写入 Flash。
+//!
+//! # RAM 占用
+//! 每个样本在缓冲区中占用 `size_of::<Option<BurstSample>>()` 字节（12 字节），
+//! 容量为 `N` 的记录器约占用 `12 * N` 字节堆内存，例如 `N = 600` 约 7KB。
+//! 采集时长超过 `N * interval` 时，只保留最近的 `N` 个样本。
+//!
+//! # 二进制格式
+//! 所有整数均为小端序：
+//! - 头部：样本数 `u32`，采样间隔毫秒 `u32`
+//! - 每个样本：相对采集开始的偏移毫秒 `u32`，随后是 `InfoSlot::as_bytes()` 的 4 字节
+
+use super::info_def::InfoSlot;
+use crate::utils::circular_queue::CircularQueue;
+use anyhow::{bail, Result};
+use std::time::{Duration, Instant};
+
+/// 单个突发采集样本
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurstSample {
+    /// 相对采集开始的偏移（毫秒）
+    pub offset_ms: u32,
+    pub slot: InfoSlot,
+}
+
+/// 突发采集记录器，按固定间隔在有限时长内采集，缓冲区满后覆盖最旧的样本
+pub struct BurstRecorder<const N: usize> {
+    samples: CircularQueue<BurstSample, N>,
+    interval: Duration,
+    duration: Duration,
+    /// 下一次采样的序号，第 `taken` 次采样在 `interval * taken` 时到期
+    taken: u32,
+}
+
+impl<const N: usize> BurstRecorder<N> {
+    /// 创建记录器
+    ///
+    /// # Arguments
+    /// * `interval` - 采样间隔，可小于 1 秒
+    /// * `duration` - 采集总时长，超过后不再记录
+    ///
+    /// `interval` 为 0 时返回错误，否则 `run` 会不间断地读取传感器
+    pub fn new(interval: Duration, duration: Duration) -> Result<Self> {
+        if interval.is_zero() {
+            bail!("突发采集的采样间隔不能为 0");
+        }
+        Ok(Self {
+            samples: CircularQueue::new(),
+            interval,
+            duration,
+            taken: 0,
+        })
+    }
+
+    /// 采集开始经过 `elapsed` 后是否已结束
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        elapsed >= self.duration
+    }
+
+    /// 采集开始经过 `elapsed` 后是否应当采样
+    pub fn is_due(&self, elapsed: Duration) -> bool {
+        !self.is_finished(elapsed) && elapsed >= self.interval * self.taken
+    }
+
+    /// 在采集开始经过 `elapsed` 时提交一个样本，未到采样时间或已结束时忽略
+    ///
+    /// # Returns
+    /// * `bool` - 样本是否被记录
+    pub fn record(&mut self, elapsed: Duration, slot: InfoSlot) -> bool {
+        if !self.is_due(elapsed) {
+            return false;
+        }
+        self.samples.push_overwrite(BurstSample {
+            offset_ms: elapsed.as_millis() as u32,
+            slot,
+        });
+        self.skip_to_next(elapsed);
+        true
+    }
+
+    /// 将下一次采样推进到 `elapsed` 之后的第一个采样时刻
+    ///
+    /// 唤醒过晚或读取过慢时跳过错过的采样，而不是连续补采
+    fn skip_to_next(&mut self, elapsed: Duration) {
+        let slot = elapsed.as_nanos() / self.interval.as_nanos();
+        self.taken = u32::try_from(slot + 1).unwrap_or(u32::MAX);
+    }
+
+    /// 阻塞执行整个采集过程，`read` 返回 `None` 表示本次读取失败，跳过该样本
+    pub fn run(&mut self, mut read: impl FnMut() -> Option<InfoSlot>) {
+        let started = Instant::now();
+        log::info!(
+            "开始突发采集: 间隔 {}ms, 时长 {}ms, 缓冲区 {N} 个样本",
+            self.interval.as_millis(),
+            self.duration.as_millis()
+        );
+        loop {
+            let elapsed = started.elapsed();
+            if self.is_finished(elapsed) {
+                break;
+            }
+            if self.is_due(elapsed) {
+                match read() {
+                    Some(slot) => {
+                        self.record(elapsed, slot);
+                    }
+                    None => {
+                        // 跳过这一次采样，等待下一个间隔
+                        self.skip_to_next(elapsed);
+                    }
+                }
+            }
+            let next_due = self.interval * self.taken;
+            std::thread::sleep(next_due.saturating_sub(started.elapsed()).min(self.duration));
+        }
+        log::info!("突发采集结束，共保留 {} 个样本", self.samples.len());
+    }
+
+    /// 当前保留的样本数
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// 按时间顺序遍历保留的样本
+    pub fn samples(&self) -> impl Iterator<Item = &BurstSample> {
+        self.samples.iter()
+    }
+
+    /// 将保留的样本序列化为二进制格式，格式见模块文档
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let slot_size = size_of::<InfoSlot>();
+        let mut out = Vec::with_capacity(8 + self.samples.len() * (4 + slot_size));
+        out.extend_from_slice(&(self.samples.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.interval.as_millis() as u32).to_le_bytes());
+        for sample in self.samples.iter() {
+            out.extend_from_slice(&sample.offset_ms.to_le_bytes());
+            out.extend_from_slice(sample.slot.as_bytes());
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以 10ms 步长模拟时间流逝，每一步都尝试提交样本
+    fn simulate<const N: usize>(recorder: &mut BurstRecorder<N>, total: Duration) -> usize {
+        let mut recorded = 0;
+        let mut elapsed = Duration::ZERO;
+        let mut value = 0.0;
+        while elapsed < total {
+            if recorder.record(elapsed, InfoSlot::new_from_f32(value, 50.0)) {
+                recorded += 1;
+                value += 1.0;
+            }
+            elapsed += Duration::from_millis(10);
+        }
+        recorded
+    }
+
+    #[test]
+    fn test_records_expected_count() {
+        let mut recorder: BurstRecorder<32> =
+            BurstRecorder::new(Duration::from_millis(100), Duration::from_secs(1)).unwrap();
+
+        // 在 0, 100, ..., 900ms 各采样一次，时长结束后不再记录
+        let recorded = simulate(&mut recorder, Duration::from_secs(2));
+        assert_eq!(recorded, 10);
+        assert_eq!(recorder.len(), 10);
+
+        let offsets: Vec<u32> = recorder.samples().map(|s| s.offset_ms).collect();
+        assert_eq!(offsets, (0..10).map(|i| i * 100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_wraps_to_latest_samples() {
+        let mut recorder: BurstRecorder<4> =
+            BurstRecorder::new(Duration::from_millis(100), Duration::from_secs(1)).unwrap();
+
+        assert_eq!(simulate(&mut recorder, Duration::from_secs(1)), 10);
+        // 只保留最近的 4 个样本
+        assert_eq!(recorder.len(), 4);
+        let temps: Vec<f32> = recorder.samples().map(|s| s.slot.get_temperature()).collect();
+        assert_eq!(temps, vec![6.0, 7.0, 8.0, 9.0]);
+    }
+
+    #[test]
+    fn test_to_bytes_layout() {
+        let mut recorder: BurstRecorder<4> =
+            BurstRecorder::new(Duration::from_millis(250), Duration::from_secs(1)).unwrap();
+        let slot = InfoSlot::new_from_f32(25.3, 60.5);
+        assert!(recorder.record(Duration::ZERO, slot));
+        assert!(recorder.record(Duration::from_millis(260), slot));
+
+        let bytes = recorder.to_bytes();
+        assert_eq!(bytes.len(), 8 + 2 * 8);
+        assert_eq!(&bytes[0..4], &2u32.to_le_bytes());
+        assert_eq!(&bytes[4..8], &250u32.to_le_bytes());
+        assert_eq!(&bytes[16..20], &260u32.to_le_bytes());
+        assert_eq!(InfoSlot::from_bytes(&bytes[20..24]), slot);
+    }
+
+    #[test]
+    fn test_late_sample_skips_missed_slots() {
+        let mut recorder: BurstRecorder<8> =
+            BurstRecorder::new(Duration::from_millis(100), Duration::from_secs(1)).unwrap();
+        let slot = InfoSlot::new_from_f32(25.0, 50.0);
+        assert!(recorder.record(Duration::ZERO, slot));
+
+        // 350ms 时才唤醒：只补采一次，下一次在 400ms
+        assert!(recorder.record(Duration::from_millis(350), slot));
+        assert!(!recorder.record(Duration::from_millis(360), slot));
+        assert!(recorder.record(Duration::from_millis(400), slot));
+    }
+
+    #[test]
+    fn test_rejects_zero_interval() {
+        assert!(BurstRecorder::<4>::new(Duration::ZERO, Duration::from_secs(1)).is_err());
+    }
+}
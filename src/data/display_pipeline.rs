@@ -0,0 +1,305 @@
+//! 读数显示管道
+//!
+//! 读数在显示前按顺序经过一组变换（校准、平滑、单位转换），
+//! 只影响屏幕上显示的数值，写入数据库的仍是原始读数。
+//! 变换按添加顺序执行，例如先转换为华氏度再校准时，校准偏移以 °F 为单位。
+
+use super::info_def::InfoSlot;
+use super::rtc_window::RtcBackedQueue;
+use crate::utils::calculate::{average_recent, MAX_SMOOTHING_WINDOW};
+use crate::utils::circular_queue::CircularQueue;
+use core::fmt;
+
+/// 温度单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// 显示用的单位符号
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    fn to_celsius(self, value: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    fn celsius_to(self, value: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => value * 9.0 / 5.0 + 32.0,
+        }
+    }
+}
+
+/// 经过管道处理、用于显示的读数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayReading {
+    pub temperature: f32,
+    /// 相对湿度（%）
+    pub humidity: f32,
+    pub unit: TemperatureUnit,
+}
+
+impl fmt::Display for DisplayReading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TEMP:{:.1}{}\nHUMD:{:.1} %",
+            self.temperature,
+            self.unit.symbol(),
+            self.humidity
+        )
+    }
+}
+
+/// 管道中的单个变换
+pub enum Transform {
+    /// 加上固定偏移，温度偏移使用当前单位；湿度结果限制在 0~100%
+    Calibrate {
+        temperature_offset: f32,
+        humidity_offset: f32,
+    },
+    /// 对最近 `window` 次经过该变换的读数求滑动平均
+    Smooth {
+        window: usize,
+        history: CircularQueue<(f32, f32), MAX_SMOOTHING_WINDOW>,
+        /// 设置后窗口会同步保存到 RTC 内存，跨深度睡眠保持
+        rtc: Option<RtcBackedQueue>,
+    },
+    /// 转换温度单位
+    Convert(TemperatureUnit),
+}
+
+impl Transform {
+    /// 创建平滑变换，窗口为 0 按 1 处理，最大为 `MAX_SMOOTHING_WINDOW`
+    pub fn smooth(window: usize) -> Self {
+        Transform::Smooth {
+            window: window.clamp(1, MAX_SMOOTHING_WINDOW),
+            history: CircularQueue::new(),
+            rtc: None,
+        }
+    }
+
+    /// 创建窗口保存在 RTC 内存中的平滑变换，并恢复深度睡眠前的窗口
+    ///
+    /// 深度睡眠会清空 RAM，唤醒后接着之前的读数平均，第一个读数不会跳变。
+    /// 窗口按 `InfoSlot` 的精度保存，放在单位转换之前时保存的是摄氏度读数
+    pub fn smooth_with_rtc(window: usize, rtc: RtcBackedQueue) -> Self {
+        let mut history = CircularQueue::new();
+        if let Some(saved) = rtc.restore::<MAX_SMOOTHING_WINDOW>() {
+            for &slot in saved.iter() {
+                history.push_overwrite(slot.into());
+            }
+        }
+        Transform::Smooth {
+            window: window.clamp(1, MAX_SMOOTHING_WINDOW),
+            history,
+            rtc: Some(rtc),
+        }
+    }
+
+    fn apply(&mut self, reading: DisplayReading) -> DisplayReading {
+        match self {
+            Transform::Calibrate {
+                temperature_offset,
+                humidity_offset,
+            } => DisplayReading {
+                temperature: reading.temperature + *temperature_offset,
+                humidity: (reading.humidity + *humidity_offset).clamp(0.0, 100.0),
+                ..reading
+            },
+            Transform::Smooth {
+                window,
+                history,
+                rtc,
+            } => {
+                history.push_overwrite((reading.temperature, reading.humidity));
+                if let Some(rtc) = rtc.as_mut() {
+                    rtc.save(&to_slots(history));
+                }
+                // 刚刚写入了一个读数，平均值必然存在
+                let (temperature, humidity) = average_recent(history, *window)
+                    .unwrap_or((reading.temperature, reading.humidity));
+                DisplayReading {
+                    temperature,
+                    humidity,
+                    ..reading
+                }
+            }
+            Transform::Convert(unit) => DisplayReading {
+                temperature: unit.celsius_to(reading.unit.to_celsius(reading.temperature)),
+                unit: *unit,
+                ..reading
+            },
+        }
+    }
+}
+
+/// 将平滑窗口转换为 `InfoSlot` 队列，用于保存到 RTC 内存
+fn to_slots<const N: usize>(history: &CircularQueue<(f32, f32), N>) -> CircularQueue<InfoSlot, N> {
+    let mut slots = CircularQueue::new();
+    for &(temperature, humidity) in history.iter() {
+        slots.push_overwrite(InfoSlot::new_from_f32(temperature, humidity));
+    }
+    slots
+}
+
+/// 由有序变换组成的显示管道，没有变换时原样显示摄氏度读数
+///
+/// # 示例
+/// ```ignore
+/// let mut pipeline = DisplayPipeline::new()
+///     .calibrate(-0.5, 2.0)
+///     .smooth(5)
+///     .convert(TemperatureUnit::Fahrenheit);
+/// let reading = pipeline.apply(&info_slot);
+/// ```
+#[derive(Default)]
+pub struct DisplayPipeline {
+    transforms: Vec<Transform>,
+}
+
+impl DisplayPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一个校准变换
+    pub fn calibrate(self, temperature_offset: f32, humidity_offset: f32) -> Self {
+        self.with(Transform::Calibrate {
+            temperature_offset,
+            humidity_offset,
+        })
+    }
+
+    /// 追加一个平滑变换
+    pub fn smooth(self, window: usize) -> Self {
+        self.with(Transform::smooth(window))
+    }
+
+    /// 追加一个窗口保存在 RTC 内存中的平滑变换，见 `Transform::smooth_with_rtc`
+    pub fn smooth_with_rtc(self, window: usize, rtc: RtcBackedQueue) -> Self {
+        self.with(Transform::smooth_with_rtc(window, rtc))
+    }
+
+    /// 追加一个单位转换变换
+    pub fn convert(self, unit: TemperatureUnit) -> Self {
+        self.with(Transform::Convert(unit))
+    }
+
+    /// 追加任意变换
+    pub fn with(mut self, transform: Transform) -> Self {
+        self.push(transform);
+        self
+    }
+
+    /// 运行时追加变换
+    pub fn push(&mut self, transform: Transform) {
+        self.transforms.push(transform);
+    }
+
+    /// 移除所有变换，恢复原样显示
+    pub fn clear(&mut self) {
+        self.transforms.clear();
+    }
+
+    /// 按顺序对读数执行所有变换
+    pub fn apply(&mut self, slot: &InfoSlot) -> DisplayReading {
+        let reading = DisplayReading {
+            temperature: slot.get_temperature(),
+            humidity: slot.get_humidity(),
+            unit: TemperatureUnit::Celsius,
+        };
+        self.transforms
+            .iter_mut()
+            .fold(reading, |reading, transform| transform.apply(reading))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_empty_pipeline_is_raw() {
+        let mut pipeline = DisplayPipeline::new();
+        let reading = pipeline.apply(&InfoSlot::new_from_f32(25.3, 60.5));
+        assert_close(reading.temperature, 25.3);
+        assert_close(reading.humidity, 60.5);
+        assert_eq!(reading.unit, TemperatureUnit::Celsius);
+        assert_eq!(reading.to_string(), "TEMP:25.3°C\nHUMD:60.5 %");
+    }
+
+    #[test]
+    fn test_transform_order() {
+        let slot = InfoSlot::new_from_f32(20.0, 50.0);
+
+        // 先校准再转换：(20 + 1) °C = 69.8 °F
+        let mut calibrate_first = DisplayPipeline::new()
+            .calibrate(1.0, 0.0)
+            .convert(TemperatureUnit::Fahrenheit);
+        let reading = calibrate_first.apply(&slot);
+        assert_close(reading.temperature, 69.8);
+        assert_eq!(reading.unit, TemperatureUnit::Fahrenheit);
+
+        // 先转换再校准：68 °F + 1 = 69 °F
+        let mut convert_first = DisplayPipeline::new()
+            .convert(TemperatureUnit::Fahrenheit)
+            .calibrate(1.0, 0.0);
+        assert_close(convert_first.apply(&slot).temperature, 69.0);
+
+        // 转换回摄氏度
+        let mut round_trip = DisplayPipeline::new()
+            .convert(TemperatureUnit::Fahrenheit)
+            .convert(TemperatureUnit::Celsius);
+        assert_close(round_trip.apply(&slot).temperature, 20.0);
+    }
+
+    #[test]
+    fn test_smooth_then_calibrate() {
+        let mut pipeline = DisplayPipeline::new().smooth(2).calibrate(-0.5, 60.0);
+
+        let first = pipeline.apply(&InfoSlot::new_from_f32(20.0, 40.0));
+        assert_close(first.temperature, 19.5);
+        // 湿度校准后限制在 100%
+        assert_close(first.humidity, 100.0);
+
+        pipeline.apply(&InfoSlot::new_from_f32(22.0, 20.0));
+        // 只平均最近 2 次：(22 + 24) / 2 - 0.5
+        let reading = pipeline.apply(&InfoSlot::new_from_f32(24.0, 30.0));
+        assert_close(reading.temperature, 22.5);
+        assert_close(reading.humidity, 85.0);
+    }
+
+    #[test]
+    fn test_smooth_window_survives_rtc_encoding() {
+        use crate::data::rtc_window::{decode_window, encode_window, RTC_WINDOW_BYTES};
+
+        let mut history: CircularQueue<(f32, f32), MAX_SMOOTHING_WINDOW> = CircularQueue::new();
+        history.push_overwrite((20.0, 40.0));
+        history.push_overwrite((21.5, 50.0));
+
+        let mut buf = [0u8; RTC_WINDOW_BYTES];
+        encode_window(&to_slots(&history), &mut buf);
+        let restored: CircularQueue<InfoSlot, MAX_SMOOTHING_WINDOW> = decode_window(&buf).unwrap();
+        let restored: Vec<(f32, f32)> = restored.iter().map(|&slot| slot.into()).collect();
+        assert_eq!(restored, vec![(20.0, 40.0), (21.5, 50.0)]);
+    }
+}
@@ -1,5 +1,34 @@
 use core::fmt;
 
+/// humidity 字段最高位：置位表示以 0.01 为单位存储（旧记录该位始终为 0）
+const HUNDREDTHS_FLAG: u16 = 0x8000;
+
+/// 温湿度的存储精度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// 0.1°C / 0.1%，DHT22 等传感器的分辨率，也是旧记录的格式
+    Tenths,
+    /// 0.01°C / 0.01%，适用于 SHT35 等高精度传感器
+    Hundredths,
+}
+
+impl Precision {
+    fn scale(self) -> f32 {
+        match self {
+            Precision::Tenths => 10.0,
+            Precision::Hundredths => 100.0,
+        }
+    }
+
+    /// 显示时保留的小数位数
+    pub fn decimals(self) -> usize {
+        match self {
+            Precision::Tenths => 1,
+            Precision::Hundredths => 2,
+        }
+    }
+}
+
 #[repr(C, align(4))]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct InfoSlot {
@@ -9,16 +38,26 @@ pub struct InfoSlot {
 
 impl fmt::Display for InfoSlot {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = self.precision().decimals();
         write!(
             f,
-            "InfoSlot {{ temperature: {:.1}°C, humidity: {:.1}% }}",
+            "InfoSlot {{ temperature: {:.*}°C, humidity: {:.*}% }}",
+            decimals,
             self.get_temperature(),
+            decimals,
             self.get_humidity()
         )
     }
 }
 
 
+/// 转换为（温度, 湿度），用于滑动平均等按数值计算的场景
+impl From<InfoSlot> for (f32, f32) {
+    fn from(slot: InfoSlot) -> Self {
+        (slot.get_temperature(), slot.get_humidity())
+    }
+}
+
 impl InfoSlot {
     // pub const SERIALIZED_SIZE: usize = std::mem::size_of::<Self>();
 
@@ -30,21 +69,85 @@ impl InfoSlot {
     // }
 
     pub fn new_from_f32(temperature: f32, humidity: f32) -> Self {
+        Self::new_with_precision(temperature, humidity, Precision::Tenths)
+    }
+
+    /// 以指定精度创建 InfoSlot
+    ///
+    /// 高精度模式下温度范围为 ±327.67°C，湿度上限为 327.67%，超出时饱和
+    pub fn new_with_precision(temperature: f32, humidity: f32, precision: Precision) -> Self {
         log::info!(
-            "Creating InfoSlot from f32: temperature = {temperature:.1}, humidity = {humidity:.1}"
+            "Creating InfoSlot from f32: temperature = {temperature:.2}, humidity = {humidity:.2}, precision = {precision:?}"
         );
-        Self {
-            temperature: (temperature * 10.0) as i16,
-            humidity: (humidity * 10.0) as u16,
+        let mut slot = Self {
+            temperature: 0,
+            humidity: match precision {
+                Precision::Tenths => 0,
+                Precision::Hundredths => HUNDREDTHS_FLAG,
+            },
+        };
+        slot.set_temperature(temperature);
+        slot.set_humidity(humidity);
+        slot
+    }
+
+    /// 当前记录的存储精度，由 humidity 最高位决定
+    pub fn precision(&self) -> Precision {
+        if self.humidity & HUNDREDTHS_FLAG != 0 {
+            Precision::Hundredths
+        } else {
+            Precision::Tenths
         }
     }
 
     pub fn get_temperature(&self) -> f32 {
-        self.temperature as f32 / 10.0
+        self.temperature as f32 / self.precision().scale()
     }
 
     pub fn get_humidity(&self) -> f32 {
-        self.humidity as f32 / 10.0
+        (self.humidity & !HUNDREDTHS_FLAG) as f32 / self.precision().scale()
+    }
+
+    /// 温度（华氏度）
+    pub fn get_temperature_fahrenheit(&self) -> f32 {
+        self.get_temperature() * 9.0 / 5.0 + 32.0
+    }
+
+    /// 露点温度（摄氏度），使用 Magnus 公式计算
+    ///
+    /// 湿度低于 0.1% 时按 0.1% 计算，避免对 0 取对数
+    pub fn get_dew_point_celsius(&self) -> f32 {
+        const A: f32 = 17.62;
+        const B: f32 = 243.12;
+        let temperature = self.get_temperature();
+        let humidity = self.get_humidity().clamp(0.1, 100.0);
+        let gamma = (humidity / 100.0).ln() + A * temperature / (B + temperature);
+        B * gamma / (A - gamma)
+    }
+
+    /// 体感温度（摄氏度），使用美国国家气象局（NWS）的 Rothfusz 回归公式
+    ///
+    /// 内部换算为华氏度计算后再换算回摄氏度。该公式仅在气温不低于 80°F（约 26.7°C）
+    /// 且相对湿度不低于 40% 时有效，超出该范围时直接返回实际温度。
+    pub fn get_heat_index_celsius(&self) -> f32 {
+        let t = self.get_temperature_fahrenheit();
+        let rh = self.get_humidity();
+        if t < 80.0 || rh < 40.0 {
+            return self.get_temperature();
+        }
+
+        let mut hi = -42.379 + 2.049_015_2 * t + 10.143_331 * rh
+            - 0.224_755_4 * t * rh
+            - 0.006_837_83 * t * t
+            - 0.054_817_17 * rh * rh
+            + 0.001_228_74 * t * t * rh
+            + 0.000_852_82 * t * rh * rh
+            - 0.000_001_99 * t * t * rh * rh;
+        // 高湿度修正
+        if rh > 85.0 && t <= 87.0 {
+            hi += (rh - 85.0) / 10.0 * ((87.0 - t) / 5.0);
+        }
+        (hi - 32.0) * 5.0 / 9.0
     }
 
     // pub fn temperature_raw(&self) -> i8 {
@@ -63,12 +166,195 @@ impl InfoSlot {
         unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Self) }
     }
 
-    // pub fn set_temperature(&mut self, temperature: f32) {
-    //     self.temperature = (temperature * 10.0) as i8;
-    // }
+    /// 按当前记录的精度设置温度
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.temperature = (temperature * self.precision().scale()).round() as i16;
+    }
 
-    // pub fn set_humidity(&mut self, humidity: f32) {
-    //     self.humidity = (humidity * 10.0) as u8;
-    // }
+    /// 按当前记录的精度设置湿度，保留精度标志位
+    pub fn set_humidity(&mut self, humidity: f32) {
+        let flag = self.humidity & HUNDREDTHS_FLAG;
+        // 饱和到 15 位，避免覆盖精度标志位
+        let raw = ((humidity * self.precision().scale()).round() as u16).min(!HUNDREDTHS_FLAG);
+        self.humidity = flag | raw;
+    }
+
+    /// 以 JSON 字段格式追加温湿度到 `out`，不含外层花括号，小数位数与记录精度一致
+    ///
+    /// 输出形如 `"temperature":25.3,"humidity":60.0`，HTTP、MQTT 和导出接口共用同一格式
+    pub fn write_json_fields(&self, out: &mut String) {
+        use core::fmt::Write;
 
+        let decimals = self.precision().decimals();
+        // 写入 String 不会失败
+        let _ = write!(
+            out,
+            r#""temperature":{:.*},"humidity":{:.*}"#,
+            decimals,
+            self.get_temperature(),
+            decimals,
+            self.get_humidity()
+        );
+    }
+}
+
+/// serde 序列化表示：温湿度以浮点数暴露，不包含内部的定点编码
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct InfoSlotRepr {
+    temperature: f32,
+    humidity: f32,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for InfoSlot {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        InfoSlotRepr {
+            temperature: self.get_temperature(),
+            humidity: self.get_humidity(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// 反序列化时按 0.1 精度存储
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InfoSlot {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = InfoSlotRepr::deserialize(deserializer)?;
+        Ok(InfoSlot::new_from_f32(repr.temperature, repr.humidity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temperature_round_trip() {
+        let mut slot = InfoSlot::new_from_f32(0.0, 50.0);
+
+        slot.set_temperature(25.3);
+        assert_eq!(slot.get_temperature(), 25.3);
+
+        slot.set_temperature(-5.0);
+        assert_eq!(slot.get_temperature(), -5.0);
+
+        // 序列化后再读取保持一致
+        let decoded = InfoSlot::from_bytes(slot.as_bytes());
+        assert_eq!(decoded, slot);
+        assert_eq!(decoded.get_temperature(), -5.0);
+    }
+
+    #[test]
+    fn test_values_beyond_i8_range() {
+        // 旧的 i8 存储在 12.7°C 以上会溢出
+        let slot = InfoSlot::new_from_f32(37.5, 45.0);
+        let decoded = InfoSlot::from_bytes(slot.as_bytes());
+        assert_eq!(decoded.get_temperature(), 37.5);
+        assert_eq!(decoded.get_humidity(), 45.0);
+    }
+
+    #[test]
+    fn test_out_of_range_saturates() {
+        let mut slot = InfoSlot::new_from_f32(0.0, 0.0);
+
+        slot.set_temperature(5000.0);
+        assert_eq!(slot.get_temperature(), i16::MAX as f32 / 10.0);
+
+        slot.set_humidity(-1.0);
+        assert_eq!(slot.get_humidity(), 0.0);
+    }
+
+    #[test]
+    fn test_hundredths_round_trip() {
+        let slot = InfoSlot::new_with_precision(23.45, 56.78, Precision::Hundredths);
+        assert_eq!(slot.precision(), Precision::Hundredths);
+        assert_eq!(slot.get_temperature(), 23.45);
+        assert_eq!(slot.get_humidity(), 56.78);
+
+        let decoded = InfoSlot::from_bytes(slot.as_bytes());
+        assert_eq!(decoded.precision(), Precision::Hundredths);
+        assert_eq!(decoded.get_temperature(), 23.45);
+        assert_eq!(decoded.get_humidity(), 56.78);
+
+        let negative = InfoSlot::new_with_precision(-0.01, 100.0, Precision::Hundredths);
+        assert_eq!(negative.get_temperature(), -0.01);
+        assert_eq!(negative.get_humidity(), 100.0);
+    }
+
+    #[test]
+    fn test_fahrenheit_and_dew_point() {
+        let slot = InfoSlot::new_from_f32(25.0, 60.0);
+        assert!((slot.get_temperature_fahrenheit() - 77.0).abs() < 1e-4);
+        assert!((slot.get_dew_point_celsius() - 16.7).abs() < 0.05);
+
+        let freezing = InfoSlot::new_from_f32(0.0, 100.0);
+        assert!((freezing.get_temperature_fahrenheit() - 32.0).abs() < 1e-4);
+        // 饱和时露点等于气温
+        assert!(freezing.get_dew_point_celsius().abs() < 1e-3);
+
+        let dry = InfoSlot::new_from_f32(20.0, 0.0);
+        assert!(dry.get_dew_point_celsius().is_finite());
+    }
+
+    #[test]
+    fn test_heat_index() {
+        // NWS 体感温度表：90°F / 60% -> 100°F
+        let slot = InfoSlot::new_from_f32(32.2, 60.0);
+        assert!((slot.get_heat_index_celsius() - 37.8).abs() < 0.5);
+
+        // 86°F / 90% -> 105°F
+        let humid = InfoSlot::new_from_f32(30.0, 90.0);
+        assert!((humid.get_heat_index_celsius() - 40.6).abs() < 0.5);
+
+        // 100°F / 40% -> 109°F
+        let hot = InfoSlot::new_from_f32(37.8, 40.0);
+        assert!((hot.get_heat_index_celsius() - 42.8).abs() < 0.5);
+
+        // 超出公式有效范围时返回实际温度
+        assert_eq!(InfoSlot::new_from_f32(25.0, 60.0).get_heat_index_celsius(), 25.0);
+        assert_eq!(InfoSlot::new_from_f32(35.0, 20.0).get_heat_index_celsius(), 35.0);
+    }
+
+    #[test]
+    fn test_legacy_tenths_decode() {
+        // 旧格式记录：温度 25.3°C (253)，湿度 60.5% (605)，小端序
+        let bytes = [253u8, 0, 93, 2];
+        let slot = InfoSlot::from_bytes(&bytes);
+        assert_eq!(slot.precision(), Precision::Tenths);
+        assert_eq!(slot.get_temperature(), 25.3);
+        assert_eq!(slot.get_humidity(), 60.5);
+    }
+
+    #[test]
+    fn test_display_uses_precision() {
+        let tenths = InfoSlot::new_from_f32(25.3, 60.0);
+        assert_eq!(tenths.to_string(), "InfoSlot { temperature: 25.3°C, humidity: 60.0% }");
+
+        let hundredths = InfoSlot::new_with_precision(25.34, 60.05, Precision::Hundredths);
+        assert_eq!(hundredths.to_string(), "InfoSlot { temperature: 25.34°C, humidity: 60.05% }");
+    }
+
+    #[test]
+    fn test_write_json_fields() {
+        let mut out = String::new();
+        InfoSlot::new_from_f32(25.3, 60.0).write_json_fields(&mut out);
+        assert_eq!(out, r#""temperature":25.3,"humidity":60.0"#);
+
+        out.clear();
+        InfoSlot::new_with_precision(-5.07, 60.05, Precision::Hundredths).write_json_fields(&mut out);
+        assert_eq!(out, r#""temperature":-5.07,"humidity":60.05"#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let slot = InfoSlot::new_from_f32(25.3, 60.5);
+        let json = serde_json::to_string(&slot).unwrap();
+        assert_eq!(json, r#"{"temperature":25.3,"humidity":60.5}"#);
+
+        let decoded: InfoSlot = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, slot);
+    }
 }
@@ -0,0 +1,170 @@
+//! 在 RTC 慢速内存中保存平滑窗口
+//!
+//! 深度睡眠会清空普通 RAM，内存中的滑动平均窗口随之丢失，唤醒后的第一个
+//! 平滑读数会出现跳变。RTC 慢速内存（ESP32-C3 共 8KB）在深度睡眠期间保持，
+//! 将窗口序列化到其中即可在唤醒后恢复。
+//!
+//! # 缓冲区格式
+//! - 头部 4 字节：魔数 `u16`（小端序），样本数 `u8`，样本字节的异或校验 `u8`
+//! - 每个样本：`InfoSlot::as_bytes()` 的 4 字节，从旧到新排列
+//!
+//! 冷启动时 RTC 内存为全零，魔数不匹配，视为没有可恢复的窗口。
+
+use super::info_def::InfoSlot;
+use crate::utils::circular_queue::CircularQueue;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// RTC 内存中最多保存的样本数
+pub const RTC_WINDOW_CAPACITY: usize = 16;
+
+const MAGIC: u16 = 0x5357; // "SW"
+const HEADER_SIZE: usize = 4;
+const SLOT_SIZE: usize = size_of::<InfoSlot>();
+
+/// 保存完整窗口所需的字节数
+pub const RTC_WINDOW_BYTES: usize = HEADER_SIZE + RTC_WINDOW_CAPACITY * SLOT_SIZE;
+
+/// 深度睡眠期间保持的缓冲区，只能通过 `RtcBackedQueue::take` 访问
+#[link_section = ".rtc.data"]
+static mut RTC_WINDOW: [u8; RTC_WINDOW_BYTES] = [0; RTC_WINDOW_BYTES];
+
+static RTC_WINDOW_TAKEN: AtomicBool = AtomicBool::new(false);
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |acc, b| acc ^ b)
+}
+
+/// 将队列中最新的样本序列化到 `buf`，放不下时丢弃最旧的样本
+///
+/// # Returns
+/// * `usize` - 写入的字节数
+pub fn encode_window<const N: usize>(queue: &CircularQueue<InfoSlot, N>, buf: &mut [u8]) -> usize {
+    if buf.len() < HEADER_SIZE {
+        return 0;
+    }
+    let fit = ((buf.len() - HEADER_SIZE) / SLOT_SIZE).min(u8::MAX as usize);
+    let count = queue.len().min(fit);
+
+    let payload = &mut buf[HEADER_SIZE..HEADER_SIZE + count * SLOT_SIZE];
+    for (chunk, slot) in payload
+        .chunks_exact_mut(SLOT_SIZE)
+        .zip(queue.iter().skip(queue.len() - count))
+    {
+        chunk.copy_from_slice(slot.as_bytes());
+    }
+    let sum = checksum(payload);
+
+    buf[0..2].copy_from_slice(&MAGIC.to_le_bytes());
+    buf[2] = count as u8;
+    buf[3] = sum;
+    HEADER_SIZE + count * SLOT_SIZE
+}
+
+/// 从 `buf` 恢复窗口，格式或校验不正确时返回 `None`；样本多于 `N` 时保留最新的 `N` 个
+pub fn decode_window<const N: usize>(buf: &[u8]) -> Option<CircularQueue<InfoSlot, N>> {
+    if buf.len() < HEADER_SIZE || buf[0..2] != MAGIC.to_le_bytes() {
+        return None;
+    }
+    let count = buf[2] as usize;
+    let payload = buf.get(HEADER_SIZE..HEADER_SIZE + count * SLOT_SIZE)?;
+    if checksum(payload) != buf[3] {
+        return None;
+    }
+
+    let mut queue = CircularQueue::new();
+    for chunk in payload.chunks_exact(SLOT_SIZE) {
+        queue.push_overwrite(InfoSlot::from_bytes(chunk));
+    }
+    Some(queue)
+}
+
+/// RTC 慢速内存中的平滑窗口，跨深度睡眠保持
+pub struct RtcBackedQueue {
+    buf: &'static mut [u8; RTC_WINDOW_BYTES],
+}
+
+impl RtcBackedQueue {
+    /// 获取 RTC 窗口缓冲区，整个程序运行期间只能获取一次
+    pub fn take() -> Option<Self> {
+        if RTC_WINDOW_TAKEN.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        // SAFETY: RTC_WINDOW_TAKEN 保证只会创建一个可变引用
+        let buf = unsafe { &mut *std::ptr::addr_of_mut!(RTC_WINDOW) };
+        Some(Self { buf })
+    }
+
+    /// 恢复深度睡眠前保存的窗口，冷启动或数据损坏时返回 `None`
+    pub fn restore<const N: usize>(&self) -> Option<CircularQueue<InfoSlot, N>> {
+        let queue = decode_window(&self.buf[..])?;
+        log::info!("从 RTC 内存恢复平滑窗口，共 {} 个样本", queue.len());
+        Some(queue)
+    }
+
+    /// 保存窗口，超出 `RTC_WINDOW_CAPACITY` 时只保存最新的样本
+    pub fn save<const N: usize>(&mut self, queue: &CircularQueue<InfoSlot, N>) {
+        encode_window(queue, &mut self.buf[..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_of<const N: usize>(temps: &[f32]) -> CircularQueue<InfoSlot, N> {
+        let mut queue = CircularQueue::new();
+        for &t in temps {
+            queue.push_overwrite(InfoSlot::new_from_f32(t, 50.0));
+        }
+        queue
+    }
+
+    fn temps<const N: usize>(queue: &CircularQueue<InfoSlot, N>) -> Vec<f32> {
+        queue.iter().map(|s| s.get_temperature()).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let queue: CircularQueue<InfoSlot, 8> = queue_of(&[20.0, 20.5, -3.2, 21.0]);
+        let mut buf = [0u8; RTC_WINDOW_BYTES];
+        assert_eq!(encode_window(&queue, &mut buf), HEADER_SIZE + 4 * SLOT_SIZE);
+
+        let restored: CircularQueue<InfoSlot, 8> = decode_window(&buf).unwrap();
+        assert_eq!(temps(&restored), vec![20.0, 20.5, -3.2, 21.0]);
+
+        // 恢复到更小的队列时保留最新的样本
+        let smaller: CircularQueue<InfoSlot, 2> = decode_window(&buf).unwrap();
+        assert_eq!(temps(&smaller), vec![-3.2, 21.0]);
+    }
+
+    #[test]
+    fn test_encode_keeps_latest_when_buffer_small() {
+        let queue: CircularQueue<InfoSlot, 8> = queue_of(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        let mut buf = [0u8; HEADER_SIZE + 2 * SLOT_SIZE + 1];
+        assert_eq!(encode_window(&queue, &mut buf), HEADER_SIZE + 2 * SLOT_SIZE);
+
+        let restored: CircularQueue<InfoSlot, 8> = decode_window(&buf).unwrap();
+        assert_eq!(temps(&restored), vec![4.0, 5.0]);
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid() {
+        // 冷启动时 RTC 内存为全零
+        assert!(decode_window::<8>(&[0u8; RTC_WINDOW_BYTES]).is_none());
+        assert!(decode_window::<8>(&[]).is_none());
+
+        let queue: CircularQueue<InfoSlot, 8> = queue_of(&[20.0, 21.0]);
+        let mut buf = [0u8; RTC_WINDOW_BYTES];
+        encode_window(&queue, &mut buf);
+
+        // 校验失败
+        let mut corrupted = buf;
+        corrupted[HEADER_SIZE] ^= 0x01;
+        assert!(decode_window::<8>(&corrupted).is_none());
+
+        // 样本数超出缓冲区
+        let mut truncated = buf;
+        truncated[2] = u8::MAX;
+        assert!(decode_window::<8>(&truncated).is_none());
+    }
+}
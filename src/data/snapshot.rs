@@ -0,0 +1,178 @@
+//! 仪表盘状态快照
+//!
+//! HTTP 接口和主屏幕需要同一组状态：最新读数、WiFi 信号强度、运行时长、存储用量和时间同步状态。
+//! `Snapshot` 集中组装这些状态，只使用已缓存的值，不遍历数据库。
+
+use super::info_def::InfoSlot;
+use super::time_db::TimeDB;
+use crate::peripherals::screen::{self, Screen};
+use crate::peripherals::wifi::rssi;
+use anyhow::Result;
+use esp_idf_svc::wifi::EspWifi;
+use ssd1306::prelude::WriteOnlyDataCommand;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// 某一时刻的仪表盘状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// 最新读数
+    pub reading: Option<InfoSlot>,
+    /// 最新读数的 unix 时间戳，时间未同步时为 `None`
+    pub reading_time: Option<i64>,
+    /// WiFi 信号强度（dBm），未连接时为 `None`
+    pub rssi: Option<i8>,
+    /// 自启动起的运行时长
+    pub uptime: Duration,
+    /// 存储已用容量百分比，尚未统计时为 `None`
+    pub storage_usage: Option<f32>,
+    /// 时间是否已同步
+    pub time_synced: bool,
+}
+
+impl Snapshot {
+    /// 从各子系统的缓存组装快照
+    ///
+    /// `time_db` 只需要共享引用：遍历数据库的方法都需要 `&mut self`，
+    /// 这里只能读取 `cached_usage_percent` 这类缓存值
+    ///
+    /// # Arguments
+    /// * `reading` - 主循环最近一次读取的读数
+    /// * `reading_time` - 该读数的 unix 时间戳，时间未同步时传 `None`
+    /// * `time_db` - 时间序列数据库
+    /// * `wifi` - WiFi 句柄，用于读取信号强度
+    /// * `boot` - 启动时刻
+    /// * `time_synced` - 时间是否已同步
+    pub fn collect(
+        reading: Option<InfoSlot>,
+        reading_time: Option<i64>,
+        time_db: &TimeDB,
+        wifi: &EspWifi<'_>,
+        boot: Instant,
+        time_synced: bool,
+    ) -> Self {
+        Self {
+            reading,
+            reading_time,
+            rssi: rssi(wifi).ok(),
+            uptime: boot.elapsed(),
+            storage_usage: time_db.cached_usage_percent(),
+            time_synced,
+        }
+    }
+
+    /// 序列化为 JSON 对象，缺失的值为 `null`
+    ///
+    /// 格式为 `{"time":1700000000,"temperature":25.3,"humidity":60.0,"rssi":-62,
+    /// "uptime":3600,"storage_usage":42.5,"time_synced":true}`，`uptime` 单位为秒
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{");
+        // 写入 String 不会失败
+        let _ = write!(out, r#""time":{},"#, json_or_null(self.reading_time));
+        match &self.reading {
+            Some(slot) => {
+                slot.write_json_fields(&mut out);
+                out.push(',');
+            }
+            None => out.push_str(r#""temperature":null,"humidity":null,"#),
+        }
+        let _ = write!(
+            out,
+            r#""rssi":{},"uptime":{},"storage_usage":{},"time_synced":{}}}"#,
+            json_or_null(self.rssi),
+            self.uptime.as_secs(),
+            json_or_null(self.storage_usage.map(|pct| format!("{pct:.1}"))),
+            self.time_synced
+        );
+        out
+    }
+
+    /// 屏幕底部的状态栏文本，例如 `-62dBm 43% 3h05m`
+    ///
+    /// 缺失的值显示为 `--`，时间未同步时在末尾加 `!`
+    pub fn status_line(&self) -> String {
+        let rssi = self.rssi.map_or("--".to_string(), |r| r.to_string());
+        let usage = self
+            .storage_usage
+            .map_or("--".to_string(), |pct| format!("{pct:.0}"));
+        let sync = if self.time_synced { "" } else { "!" };
+        format!(
+            "{rssi}dBm {usage}% {}{sync}",
+            format_uptime_short(self.uptime)
+        )
+    }
+
+    /// 在屏幕底部绘制状态栏，不清屏也不刷新
+    pub fn draw<DI: WriteOnlyDataCommand>(&self, screen: &mut Screen<DI>) -> Result<()> {
+        screen.draw_text(&self.status_line(), screen::to_point(1, 62))
+    }
+}
+
+fn json_or_null(value: Option<impl ToString>) -> String {
+    value.map_or("null".to_string(), |v| v.to_string())
+}
+
+/// 将运行时长格式化为不超过 6 个字符的短文本：`42m`、`3h05m`、`2d07h`
+fn format_uptime_short(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{days}d{hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h{minutes:02}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            reading: Some(InfoSlot::new_from_f32(25.3, 60.0)),
+            reading_time: Some(1_700_000_000),
+            rssi: Some(-62),
+            uptime: Duration::from_secs(3 * 3600 + 5 * 60 + 7),
+            storage_usage: Some(42.54),
+            time_synced: true,
+        }
+    }
+
+    #[test]
+    fn test_to_json_shape() {
+        assert_eq!(
+            snapshot().to_json(),
+            r#"{"time":1700000000,"temperature":25.3,"humidity":60.0,"rssi":-62,"uptime":11107,"storage_usage":42.5,"time_synced":true}"#
+        );
+
+        let empty = Snapshot {
+            reading: None,
+            reading_time: None,
+            rssi: None,
+            uptime: Duration::ZERO,
+            storage_usage: None,
+            time_synced: false,
+        };
+        assert_eq!(
+            empty.to_json(),
+            r#"{"time":null,"temperature":null,"humidity":null,"rssi":null,"uptime":0,"storage_usage":null,"time_synced":false}"#
+        );
+    }
+
+    #[test]
+    fn test_status_line() {
+        assert_eq!(snapshot().status_line(), "-62dBm 43% 3h05m");
+
+        let unsynced = Snapshot {
+            rssi: None,
+            storage_usage: None,
+            uptime: Duration::from_secs(2 * 86400 + 7 * 3600),
+            time_synced: false,
+            ..snapshot()
+        };
+        assert_eq!(unsynced.status_line(), "--dBm --% 2d07h!");
+        assert_eq!(format_uptime_short(Duration::from_secs(59)), "0m");
+    }
+}
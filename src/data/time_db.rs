@@ -1,9 +1,13 @@
 use super::info_def;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use flashdb_rs::{tsdb::TSDB};
 use crate::peripherals::flash;
 use crate::utils::calculate;
+use crate::utils::time::{format_timestamp_in, TimeUnit, TimestampMode};
 use embedded_io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
 
 pub struct TimeDB {
     db: Box<TSDB<flash::Flash>>,
@@ -11,10 +15,81 @@ pub struct TimeDB {
     slot_size: usize,
     /// 容量警戒线百分比 (0-100)，默认为 80%
     capacity_threshold: f32,
+    /// 自启动起成功写入的逻辑记录数
+    records_written: usize,
+    /// 底层 Flash 的物理写入/擦除统计
+    flash_stats: Arc<flash::FlashStats>,
+    /// 根据插入时间戳估计的写入速率
+    insert_rate: RateEstimator,
+    /// 最近一次插入时统计的已用字节数，启动后尚未插入或执行过删除时为 `None`
+    used_bytes: Option<usize>,
+    /// 增量数据校验的进度
+    scrubber: Scrubber,
+    /// 时间戳单位，默认为秒
+    time_unit: TimeUnit,
+}
+
+// SAFETY: `TSDB` 内部保存了指向自身字段的指针，因此被标记为 `!Send`；
+// 这里 `TSDB` 放在 `Box` 中，`TimeDB` 在线程间移动时其地址不变。
+// `Flash` 中的分区指针指向 ESP-IDF 的静态分区表，在任意线程中都有效。
+// `TimeDB` 的所有操作都需要 `&mut self`，跨线程共享时必须放在 `Mutex` 中。
+unsafe impl Send for TimeDB {}
+
+/// 数据库名称的最大字节数
+///
+/// FlashDB 的 TSDB 名称只作为 C 字符串保存（`FDB_KV_NAME_MAX` 限制的是 KVDB 的键名，与此无关），
+/// 本身没有长度限制；这里是项目自定的上限，用于约束日志和接口中出现的名称长度
+pub const MAX_DB_NAME_LEN: usize = 64;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DbNameError {
+    #[error("数据库名称不能为空")]
+    Empty,
+    #[error("数据库名称过长: {len} 字节，最多 {MAX_DB_NAME_LEN} 字节")]
+    TooLong { len: usize },
+    #[error("数据库名称包含非法字符 {ch:?}（位置 {index}），只允许 ASCII 字母、数字、'_' 和 '-'")]
+    InvalidChar { ch: char, index: usize },
+}
+
+/// 校验数据库名称：非空、不超过 `MAX_DB_NAME_LEN` 字节，且只包含 ASCII 字母、数字、'_' 和 '-'
+pub fn validate_db_name(name: &str) -> Result<(), DbNameError> {
+    if name.is_empty() {
+        return Err(DbNameError::Empty);
+    }
+    if let Some((index, ch)) = name
+        .char_indices()
+        .find(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+    {
+        return Err(DbNameError::InvalidChar { ch, index });
+    }
+    if name.len() > MAX_DB_NAME_LEN {
+        return Err(DbNameError::TooLong { len: name.len() });
+    }
+    Ok(())
+}
+
+/// 修复数据库名称：非法字符替换为 '_' 并截断到 `MAX_DB_NAME_LEN`，空名称替换为 "db"
+pub fn sanitize_db_name(name: &str) -> String {
+    let repaired: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .take(MAX_DB_NAME_LEN)
+        .collect();
+    if repaired.is_empty() {
+        "db".to_string()
+    } else {
+        repaired
+    }
 }
 
 impl TimeDB {
+    /// 创建或打开时间序列数据库
+    ///
+    /// `name` 必须满足 `validate_db_name` 的要求，否则返回 `DbNameError`，
+    /// 可以先用 `sanitize_db_name` 修复
     pub fn new(name: &str, max_len: u32, reset_if_size_incompatible: bool) -> Result<Self> {
+        validate_db_name(name)?;
+
         let mut slots_size = size_of::<info_def::InfoSlot>();
         // slots_size向4的整数倍取整，如果是整数则+4
         if slots_size & 0b11 != 0 {
@@ -22,7 +97,14 @@ impl TimeDB {
         } else {
             slots_size += 4;
         }
-        let max_size = calculate::quick_align((max_len * slots_size as u32 * 6 / 5) as usize, 4096);
+        // max_len 由调用方传入，乘法和对齐都可能溢出
+        let Some(max_size) = (max_len as usize)
+            .checked_mul(slots_size)
+            .and_then(|bytes| bytes.checked_mul(6))
+            .and_then(|bytes| calculate::checked_align(bytes / 5, 4096))
+        else {
+            bail!("数据库容量过大: max_len={max_len}");
+        };
         log::info!(
             "创建时间序列数据库: slot_size={slots_size}, max_size={max_size}"
         );
@@ -42,6 +124,7 @@ impl TimeDB {
             true
         };
         let storage = flash::Flash::new(max_size, reset_if_size_incompatible && should_reset)?;
+        let flash_stats = storage.stats();
         
         let mut db = Box::new(TSDB::new(storage));
         db.set_name(name)?;
@@ -51,18 +134,192 @@ impl TimeDB {
             max_size,
             slot_size: slots_size,
             capacity_threshold: 80.0, // 默认 80% 触发清理
+            records_written: 0,
+            flash_stats,
+            insert_rate: RateEstimator::new(INSERT_RATE_ALPHA),
+            used_bytes: None,
+            scrubber: Scrubber::default(),
+            time_unit: TimeUnit::Seconds,
         })
     }
 
-    pub fn insert(&mut self, timestamp: i64, value: &info_def::InfoSlot) -> Result<()> {
+    /// 设置时间戳单位，默认为秒
+    ///
+    /// 同一秒内需要写入多条记录时使用 `TimeUnit::Millis`，并用 `get_unix_timestamp_millis` 生成时间戳，
+    /// 查询和导出的时间范围也按该单位传入。单位不会保存在 Flash 中，
+    /// 已有数据的数据库改变单位后旧记录的时间会错乱，请先 `clear`。
+    pub fn with_time_unit(mut self, unit: TimeUnit) -> Self {
+        self.time_unit = unit;
+        self
+    }
+
+    /// 时间戳单位
+    pub fn time_unit(&self) -> TimeUnit {
+        self.time_unit
+    }
+
+    /// 关闭数据库
+    ///
+    /// 反初始化 TSDB 并释放底层 Flash 分区，重启或重新配置前调用，
+    /// 使资源的释放顺序明确而不依赖 drop 顺序。
+    pub fn close(self) -> Result<()> {
+        // TSDB 在 drop 时执行 fdb_tsdb_deinit，随后释放其持有的 Flash
+        drop(self.db);
+        log::info!("时间序列数据库已关闭");
+        Ok(())
+    }
+
+    /// 插入一条记录，时间戳单位见 `with_time_unit`
+    ///
+    /// 时间戳不晚于最新记录时（例如两次快速读取之间时钟未前进）跳过本次写入，
+    /// 返回 `InsertOutcome::SkippedDuplicate`，避免出现重复时间戳
+    pub fn insert(&mut self, timestamp: i64, value: &info_def::InfoSlot) -> Result<InsertOutcome> {
+        let last_time = self.db.last_time();
+        if timestamp <= last_time {
+            log::warn!("时间戳 {timestamp} 不晚于最新记录 {last_time}，跳过写入");
+            return Ok(InsertOutcome::SkippedDuplicate);
+        }
+
         // 检查容量，如果需要则清理最旧的数据
-        self.cleanup_if_needed()?;
-        
+        self.cleanup_if_needed(0)?;
+        self.append(timestamp, value)?;
+        Ok(InsertOutcome::Stored)
+    }
+
+    /// 批量插入记录，返回实际写入的条数
+    ///
+    /// 只在写入前检查一次容量（预留整批记录的空间），避免逐条 `insert` 时每条记录都遍历一次数据库。
+    /// 与 `insert` 相同，时间戳不晚于之前记录（包括批次内前面的记录）的项会被跳过。
+    /// FlashDB 不支持事务，中途写入失败时已写入的记录会保留。
+    pub fn insert_batch(&mut self, records: &[(i64, info_def::InfoSlot)]) -> Result<usize> {
+        let accepted = increasing_after(self.db.last_time(), records);
+        if accepted.len() < records.len() {
+            log::warn!(
+                "批量写入中 {} 条记录的时间戳未递增，已跳过",
+                records.len() - accepted.len()
+            );
+        }
+        if accepted.is_empty() {
+            return Ok(0);
+        }
+
+        // 最后一条记录与 `insert` 一样在写入前检查，其余记录预留空间
+        self.cleanup_if_needed(accepted.len() - 1)?;
+        for (timestamp, value) in &accepted {
+            self.append(*timestamp, value)?;
+        }
+        log::info!("已批量写入 {} 条记录", accepted.len());
+        Ok(accepted.len())
+    }
+
+    /// 写入一条记录并更新统计，调用前需确保时间戳递增且容量充足
+    fn append(&mut self, timestamp: i64, value: &info_def::InfoSlot) -> Result<()> {
         let data = value.as_bytes();
+        #[cfg(feature = "fault-inject")]
+        let data = if crate::utils::fault_inject::take(
+            crate::utils::fault_inject::Fault::StorageCorrupt,
+        ) {
+            &crate::utils::fault_inject::CORRUPT_RECORD[..]
+        } else {
+            data
+        };
         self.db.append_with_timestamp(timestamp, data)?;
+        self.used_bytes = self.used_bytes.map(|used| used + self.slot_size);
+        self.records_written += 1;
+        self.insert_rate.record(timestamp);
         Ok(())
     }
 
+    /// 根据最近插入时间戳的间隔（EWMA）估计的写入速率（条/小时），插入少于两条时返回 `None`
+    pub fn insert_rate_per_hour(&self) -> Option<f32> {
+        // 估计器按时间戳单位计算间隔，换算为每小时
+        let ticks_per_sec = self.time_unit.ticks_per_sec() as f32;
+        self.insert_rate.per_hour().map(|rate| rate * ticks_per_sec)
+    }
+
+    /// 估计按给定写入速率多久后达到容量警戒线
+    ///
+    /// 达到警戒线后 `insert` 会开始清理最旧的数据，之后数据库不会真正写满。
+    /// 速率不大于 0（或不是有限值）时永远不会写满，返回 `None`；
+    /// 速率极小、结果超出 `Duration` 范围时返回 `Duration::MAX`。
+    /// 可以传入 `insert_rate_per_hour()` 的结果。
+    pub fn time_to_full(&mut self, recent_insert_rate_per_hour: f32) -> Option<Duration> {
+        let used_bytes = self.get_current_size();
+        self.time_to_full_from(used_bytes, recent_insert_rate_per_hour)
+    }
+
+    /// 同 `time_to_full`，但使用调用方已统计的已用字节数，不遍历数据库
+    pub fn time_to_full_from(
+        &self,
+        used_bytes: usize,
+        recent_insert_rate_per_hour: f32,
+    ) -> Option<Duration> {
+        let threshold_records =
+            (self.max_size as f32 * self.capacity_threshold / 100.0) as usize / self.slot_size;
+        let current_records = used_bytes / self.slot_size;
+        time_to_fill(
+            threshold_records.saturating_sub(current_records),
+            recent_insert_rate_per_hour,
+        )
+    }
+
+    /// 写放大系数：物理写入与擦除的总字节数 / 逻辑写入的记录字节数
+    ///
+    /// 统计自启动（或数据库创建）起累计，包含 TSDB 索引写入和清理引起的扇区擦除，
+    /// 可用于评估容量冗余系数和清理比例对 Flash 寿命的影响。尚未写入任何记录时返回 0。
+    pub fn write_amplification(&self) -> f32 {
+        write_amplification(
+            self.records_written * size_of::<info_def::InfoSlot>(),
+            self.flash_stats.bytes_written(),
+            self.flash_stats.bytes_erased(),
+        )
+    }
+
+    /// 已用容量百分比（0-100），取最近一次插入时的统计值，不遍历数据库
+    ///
+    /// 启动后尚未插入记录，或执行过 `delete_range`/`clear` 后，返回 `None`
+    pub fn cached_usage_percent(&self) -> Option<f32> {
+        self.used_bytes
+            .map(|used| Usage::new(used, self.max_size).pct)
+    }
+
+    /// 当前存储用量，`used_bytes` 只统计未删除的记录，遍历一次数据库
+    pub fn usage(&mut self) -> Usage {
+        Usage::new(self.get_current_size(), self.max_size)
+    }
+
+    /// 汇总存储健康状态：用量、首尾记录时间、写入速率、写放大系数和最近一轮校验结果
+    ///
+    /// 只使用缓存值和首尾两条记录，没有用量缓存时才遍历一次数据库
+    pub fn storage_report(&mut self) -> StorageReport {
+        let usage = self.cached_usage();
+        let insert_rate_per_hour = self.insert_rate_per_hour();
+        StorageReport {
+            records: usage.used_bytes / self.slot_size,
+            usage,
+            oldest_time: self.earliest_with_time().map(|(time, _)| time),
+            newest_time: self.latest_with_time().map(|(time, _)| time),
+            insert_rate_per_hour,
+            time_to_full: insert_rate_per_hour
+                .and_then(|rate| self.time_to_full_from(usage.used_bytes, rate)),
+            write_amplification: self.write_amplification(),
+            last_scrub: self.last_scrub_report(),
+        }
+    }
+
+    /// 存储用量，优先使用插入时缓存的统计值，没有缓存时遍历一次数据库并缓存结果
+    pub fn cached_usage(&mut self) -> Usage {
+        let used = match self.used_bytes {
+            Some(used) => used,
+            None => {
+                let used = self.get_current_size();
+                self.used_bytes = Some(used);
+                used
+            }
+        };
+        Usage::new(used, self.max_size)
+    }
+
     /// 计算当前数据库的使用大小（字节）
     fn get_current_size(&mut self) -> usize {
         let mut size = 0;
@@ -73,20 +330,23 @@ impl TimeDB {
         size
     }
 
-    /// 如果容量超过警戒线，标记最旧的数据块为删除
+    /// 如果容量（加上即将写入的 `pending` 条记录）超过警戒线，标记最旧的数据块为删除
     /// 采用标记方式，实际删除由 flashdb_rs 异步处理
-    fn cleanup_if_needed(&mut self) -> Result<()> {
-        let current_size = self.get_current_size();
-        let threshold_size = (self.max_size as f32 * self.capacity_threshold / 100.0) as usize;
+    ///
+    /// 同时刷新 `used_bytes` 缓存
+    fn cleanup_if_needed(&mut self, pending: usize) -> Result<()> {
+        let pending_size = pending * self.slot_size;
+        let used_size = self.get_current_size();
+        self.used_bytes = Some(used_size);
 
-        if current_size >= threshold_size {
+        if let Some(cleanup_size) =
+            cleanup_plan(used_size, pending_size, self.max_size, self.capacity_threshold)
+        {
             log::warn!(
-                "数据库容量接近上限 (当前: {}B, 警戒线: {}B), 开始清理最旧的数据",
-                current_size, threshold_size
+                "数据库容量接近上限 (当前: {}B, 警戒线: {:.1}%), 开始清理最旧的数据",
+                used_size + pending_size, self.capacity_threshold
             );
-            
-            // 标记最旧的 10% 的数据为删除
-            let cleanup_size = (self.max_size as f32 * 0.1) as usize;
+
             let mut cleaned_size = 0;
             let mut cleanup_count = 0;
 
@@ -111,31 +371,261 @@ impl TimeDB {
                 "已标记 {} 条记录为删除 (约 {}B)",
                 cleanup_count, cleaned_size
             );
+            self.used_bytes = Some(used_size.saturating_sub(cleaned_size));
         }
 
         Ok(())
     }
 
-    // 设置容量警戒线百分比
-    // pub fn set_capacity_threshold(&mut self, threshold: f32) {
-    //     self.capacity_threshold = threshold.max(1.0).min(100.0);
-    // }
+    /// 设置容量警戒线百分比，超出 `[1.0, 100.0]` 的值会被限制到边界
+    ///
+    /// 容量达到警戒线后，`insert` 会先清理最旧的数据
+    pub fn set_capacity_threshold(&mut self, pct: f32) {
+        self.capacity_threshold = clamp_threshold(pct);
+        log::info!("容量警戒线已设置为 {:.1}%", self.capacity_threshold);
+    }
 
-    // pub fn get_by_time(&mut self, left: i64, right: i64) -> Vec<info_def::InfoSlot> {
-    //     let mut result = Vec::new();
-    //     self.db.tsdb_iter_by_time(left, right, |db, tsl| {
-    //         let mut cur = db.open_read(tsl.clone());
-    //         let mut buf = vec![0u8; size_of::<info_def::InfoSlot>()];
-    //         if cur.read(buf.as_mut_slice()).is_ok() {
-    //             let slot = info_def::InfoSlot::from_bytes(buf.as_slice());
-    //             result.push(slot);
-    //         } else {
-    //             log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
-    //         }
-    //         true
-    //     });
-    //     result
-    // }
+    /// 当前容量警戒线百分比
+    pub fn capacity_threshold(&self) -> f32 {
+        self.capacity_threshold
+    }
+
+    /// 返回时间范围 `[left, right]` 内的所有记录
+    ///
+    /// 记录数量不受限制，范围较大时请使用 `get_by_time_bounded` 避免耗尽堆内存
+    pub fn get_by_time(&mut self, left: i64, right: i64) -> Vec<info_def::InfoSlot> {
+        self.get_by_time_bounded(left, right, usize::MAX).0
+    }
+
+    /// 返回时间范围 `[left, right]` 内最多 `max_records` 条记录
+    ///
+    /// 达到上限后立即停止迭代，返回值的第二项表示结果是否被截断
+    pub fn get_by_time_bounded(
+        &mut self,
+        left: i64,
+        right: i64,
+        max_records: usize,
+    ) -> (Vec<info_def::InfoSlot>, bool) {
+        let mut collector = BoundedCollector::new(max_records);
+        self.db.tsdb_iter_by_time(left, right, |db, tsl| {
+            if collector.is_full() {
+                collector.mark_truncated();
+                return false;
+            }
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = vec![0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(buf.as_mut_slice()).is_ok() {
+                let slot = info_def::InfoSlot::from_bytes(buf.as_slice());
+                collector.push(slot);
+            } else {
+                log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+            }
+            true
+        });
+        if collector.truncated {
+            log::warn!("时间范围 [{left}, {right}] 内的记录超过 {max_records} 条，结果已截断");
+        }
+        (collector.items, collector.truncated)
+    }
+
+    /// 统计时间范围 `[left, right]` 内记录的最小/最大/平均值，范围内没有记录时返回 `None`
+    ///
+    /// 通过 `for_each_in_range` 在一次遍历中完成计算，不收集记录，适合在堆内存有限时统计大范围数据
+    pub fn aggregate(&mut self, left: i64, right: i64) -> Option<Aggregate> {
+        let mut acc = AggregateAccumulator::default();
+        self.for_each_in_range(left, right, |slot| acc.push(slot));
+        acc.finish()
+    }
+
+    /// 将时间范围 `[left, right]` 等分为 `buckets` 个区间，每个非空区间输出一条平均后的记录
+    ///
+    /// 只遍历一次数据库，结果按时间从旧到新排列。没有记录的区间被跳过而不是补零，
+    /// 调用方可据此识别数据缺失。适合将历史数据直接映射到屏幕的像素列。
+    pub fn downsample(&mut self, left: i64, right: i64, buckets: usize) -> Vec<info_def::InfoSlot> {
+        if buckets == 0 || right < left {
+            return Vec::new();
+        }
+        let mut accumulators: Vec<AggregateAccumulator> =
+            (0..buckets).map(|_| AggregateAccumulator::default()).collect();
+        self.db.tsdb_iter_by_time(left, right, |db, tsl| {
+            let Some(index) = bucket_index(tsl.time(), left, right, buckets) else {
+                return true;
+            };
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = [0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(&mut buf).is_ok() {
+                accumulators[index].push(&info_def::InfoSlot::from_bytes(&buf));
+            } else {
+                log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+            }
+            true
+        });
+        accumulators
+            .into_iter()
+            .filter_map(AggregateAccumulator::finish_slot)
+            .collect()
+    }
+
+    /// 按时间顺序扫描所有记录，返回相邻记录间隔超过 `expected_interval + tolerance` 的缺口
+    ///
+    /// 每个缺口表示为 `(gap_start, gap_end)`，即缺口前后两条记录的时间戳。
+    /// 记录少于两条时返回空列表。
+    pub fn find_gaps(&mut self, expected_interval: i64, tolerance: i64) -> Vec<(i64, i64)> {
+        let mut detector = GapDetector::new(expected_interval.saturating_add(tolerance));
+        let mut gaps = Vec::new();
+        self.db.tsdb_iter(|_db, tsl| {
+            if let Some(gap) = detector.push(tsl.time()) {
+                gaps.push(gap);
+            }
+            true
+        }, false);
+        if !gaps.is_empty() {
+            log::warn!("检测到 {} 处数据缺失", gaps.len());
+        }
+        gaps
+    }
+
+    /// 扫描所有记录，返回出现不止一次的时间戳（升序，每个只出现一次）
+    pub fn find_duplicate_timestamps(&mut self) -> Vec<i64> {
+        let mut timestamps = Vec::new();
+        self.db.tsdb_iter(|_db, tsl| {
+            timestamps.push(tsl.time());
+            true
+        }, false);
+        let duplicates = duplicate_timestamps(timestamps);
+        if !duplicates.is_empty() {
+            log::warn!("检测到 {} 个重复的时间戳: {duplicates:?}", duplicates.len());
+        }
+        duplicates
+    }
+
+    /// 增量校验数据库中的记录，每次调用最多读取 `per_cycle` 条，`per_cycle` 为 0 时不做任何事
+    ///
+    /// 从上次停下的位置继续，读取失败或数值不合理（非有限值、湿度超出 0~100%）的记录计为损坏。
+    /// 遍历完所有记录时完成一轮校验，打印汇总并返回本轮结果，否则返回 `None`。
+    pub fn scrub_step(&mut self, per_cycle: usize) -> Option<ScrubReport> {
+        if per_cycle == 0 {
+            return None;
+        }
+        let from = self.scrubber.next_from();
+        let to = self.db.last_time();
+        let mut batch = Vec::with_capacity(per_cycle);
+        if from <= to {
+            self.db.tsdb_iter_by_time(from, to, |db, tsl| {
+                let mut cur = db.open_read(tsl.clone());
+                let mut buf = [0u8; size_of::<info_def::InfoSlot>()];
+                let valid = matches!(cur.read(&mut buf), Ok(n) if n == buf.len())
+                    && record_is_plausible(&info_def::InfoSlot::from_bytes(&buf));
+                if !valid {
+                    log::warn!("数据校验发现损坏的记录: tsl={tsl:?}");
+                }
+                batch.push((tsl.time(), valid));
+                batch.len() < per_cycle
+            });
+        }
+
+        let report = self.scrubber.advance(&batch, per_cycle)?;
+        log::info!(
+            "完成一轮数据校验: 有效 {} 条，损坏 {} 条",
+            report.valid,
+            report.corrupt
+        );
+        Some(report)
+    }
+
+    /// 最近一次完整校验的结果，尚未完成过一轮时返回 `None`
+    pub fn last_scrub_report(&self) -> Option<ScrubReport> {
+        self.scrubber.last_pass
+    }
+
+    /// 将时间范围 `[left, right]` 内的记录导出为 JSON 数组
+    ///
+    /// 在遍历过程中直接写入字符串，不先收集记录，格式为
+    /// `[{"time":1700000000,"temperature":25.3,"humidity":60.0},...]`
+    pub fn export_json(&mut self, left: i64, right: i64) -> String {
+        self.export_json_with_mode(left, right, TimestampMode::UnixSeconds)
+    }
+
+    /// 同 `export_json`，`time` 字段按 `mode` 输出（unix 秒/毫秒或相对自定义纪元）
+    pub fn export_json_with_mode(&mut self, left: i64, right: i64, mode: TimestampMode) -> String {
+        self.export_json_limited(left, right, mode, usize::MAX).0
+    }
+
+    /// 同 `export_json`，最多导出 `max_records` 条记录
+    ///
+    /// 达到上限后停止遍历，避免大范围查询一次性占满堆内存
+    ///
+    /// # Returns
+    /// * `(String, bool)` - JSON 数组，以及结果是否因达到上限而被截断
+    pub fn export_json_bounded(
+        &mut self,
+        left: i64,
+        right: i64,
+        max_records: usize,
+    ) -> (String, bool) {
+        self.export_json_limited(left, right, TimestampMode::UnixSeconds, max_records)
+    }
+
+    fn export_json_limited(
+        &mut self,
+        left: i64,
+        right: i64,
+        mode: TimestampMode,
+        max_records: usize,
+    ) -> (String, bool) {
+        let mut array = BoundedJsonArray::new(max_records);
+        let unit = self.time_unit;
+        self.db.tsdb_iter_by_time(left, right, |db, tsl| {
+            if array.is_full() {
+                array.mark_truncated();
+                return false;
+            }
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = [0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(&mut buf).is_ok() {
+                array.push(
+                    format_timestamp_in(tsl.time(), unit, mode),
+                    &info_def::InfoSlot::from_bytes(&buf),
+                );
+            } else {
+                log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+            }
+            true
+        });
+        let count = array.count;
+        let (out, truncated) = array.finish();
+        if truncated {
+            log::warn!("JSON 导出达到上限 {max_records} 条，结果已截断 ({}B)", out.len());
+        } else {
+            log::info!("已导出 {count} 条记录为 JSON ({}B)", out.len());
+        }
+        (out, truncated)
+    }
+
+    /// 将所有记录按时间从旧到新导出为 CSV，写入 `writer`
+    ///
+    /// 首行为表头 `timestamp,temperature_c,humidity_pct`，温湿度保留一位小数。
+    /// 边遍历边写入，不先收集记录；写入失败时停止遍历并返回错误。
+    pub fn export_csv<W: std::io::Write + Send>(&mut self, writer: &mut W) -> Result<()> {
+        writer.write_all(CSV_HEADER.as_bytes())?;
+        let mut count = 0;
+        let mut result = Ok(());
+        self.db.tsdb_iter(|db, tsl| {
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = [0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(&mut buf).is_err() {
+                log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+                return true;
+            }
+            result = write_csv_row(writer, tsl.time(), &info_def::InfoSlot::from_bytes(&buf));
+            count += 1;
+            result.is_ok()
+        }, false);
+        result?;
+        writer.flush()?;
+        log::info!("已导出 {count} 条记录为 CSV");
+        Ok(())
+    }
 
     pub fn latest(&mut self) -> Option<info_def::InfoSlot> {
         let mut tmp: Option<info_def::InfoSlot> = None;
@@ -151,25 +641,194 @@ impl TimeDB {
         tmp
     }
 
-    // pub fn delete_range(&mut self, left: i64, right: i64) -> Result<()> {
-    //     self.db.tsdb_iter_by_time(left, right, |db, tsl| {
-    //         if let Err(e) = db.set_status(tsl, flashdb_rs::TSLStatus::Deleted) {
-    //             log::error!("删除时间槽失败: {e:?}");
-    //         }
-    //         true
-    //     });
-    //     Ok(())
-    // }
+    /// 返回最新的一条记录及其时间戳，数据库为空时返回 `None`
+    pub fn latest_with_time(&mut self) -> Option<(i64, info_def::InfoSlot)> {
+        // from > to 时 FlashDB 反向遍历，从最新的记录开始
+        self.first_readable(i64::MAX, i64::MIN)
+    }
 
-    // pub fn clear(&mut self) -> Result<()> {
-    //     self.db.tsdb_iter(|db, tsl| {
-    //         if let Err(e) = db.set_status(tsl, flashdb_rs::TSLStatus::Deleted) {
-    //             log::error!("清空时间槽失败: {e:?}");
-    //         }
-    //         true
-    //     }, false);
-    //     Ok(())
-    // }
+    /// 返回最旧的一条记录及其时间戳，数据库为空时返回 `None`
+    pub fn earliest_with_time(&mut self) -> Option<(i64, info_def::InfoSlot)> {
+        self.first_readable(i64::MIN, i64::MAX)
+    }
+
+    /// 返回最新的最多 `n` 条记录，按时间从旧到新排列
+    ///
+    /// 从最新的记录反向遍历，取满 `n` 条后立即停止；记录不足 `n` 条时返回全部记录。
+    /// 用于重启后从历史数据恢复内存中的滑动窗口。
+    pub fn recent(&mut self, n: usize) -> Vec<info_def::InfoSlot> {
+        let mut collector = BoundedCollector::new(n);
+        self.db.tsdb_iter(|db, tsl| {
+            if collector.is_full() {
+                return false;
+            }
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = vec![0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(buf.as_mut_slice()).is_ok() {
+                collector.push(info_def::InfoSlot::from_bytes(buf.as_slice()));
+            } else {
+                log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+            }
+            true
+        }, true);
+        let mut items = collector.items;
+        items.reverse();
+        items
+    }
+
+    /// 返回时间戳与 `timestamp` 最接近的记录及其时间戳，数据库为空时返回 `None`
+    ///
+    /// 写入时保证时间戳严格递增，因此只需分别从 `timestamp` 向后、向前各找到第一条记录，
+    /// 无需遍历整个数据库；存在完全匹配的记录时直接返回。两侧距离相等时返回较早的记录。
+    pub fn find_nearest(&mut self, timestamp: i64) -> Option<(i64, info_def::InfoSlot)> {
+        let after = self.first_readable(timestamp, self.db.last_time().max(timestamp));
+        if after.is_some_and(|(time, _)| time == timestamp) {
+            return after;
+        }
+        // from > to 时 FlashDB 反向遍历
+        let before = self.first_readable(timestamp, i64::MIN);
+        nearest(timestamp, before, after)
+    }
+
+    /// 按 `from` 到 `to` 的方向遍历，返回第一条成功读取的记录
+    fn first_readable(&mut self, from: i64, to: i64) -> Option<(i64, info_def::InfoSlot)> {
+        let mut found = None;
+        self.db.tsdb_iter_by_time(from, to, |db, tsl| {
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = [0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(&mut buf).is_ok() {
+                found = Some((tsl.time(), info_def::InfoSlot::from_bytes(&buf)));
+                return false;
+            }
+            log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+            true
+        });
+        found
+    }
+
+    /// 返回最旧的一条记录，数据库为空时返回 `None`
+    ///
+    /// 从最旧的记录开始遍历，跳过读取失败的记录，返回第一条成功解码的数据
+    pub fn earliest(&mut self) -> Option<info_def::InfoSlot> {
+        let mut tmp: Option<info_def::InfoSlot> = None;
+        self.db.tsdb_iter(|db, tsl| {
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = vec![0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(buf.as_mut_slice()).is_ok() {
+                tmp = Some(info_def::InfoSlot::from_bytes(buf.as_slice()));
+                // 找到后停止遍历
+                return false;
+            }
+            log::warn!("读取最旧记录失败，继续查找下一条: tsl={tsl:?}");
+            true
+        }, false);
+        tmp
+    }
+
+    /// 从最旧的记录开始逐条回调，不收集记录，适合求和或直接绘制等只需遍历一次的场景
+    pub fn for_each_record(&mut self, mut f: impl FnMut(&info_def::InfoSlot) + Send) {
+        self.db.tsdb_iter(|db, tsl| {
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = [0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(&mut buf).is_ok() {
+                f(&info_def::InfoSlot::from_bytes(&buf));
+            } else {
+                log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+            }
+            true
+        }, false);
+    }
+
+    /// 同 `for_each_record`，只遍历时间范围 `[left, right]` 内的记录
+    pub fn for_each_in_range(
+        &mut self,
+        left: i64,
+        right: i64,
+        mut f: impl FnMut(&info_def::InfoSlot) + Send,
+    ) {
+        self.db.tsdb_iter_by_time(left, right, |db, tsl| {
+            let mut cur = db.open_read(tsl.clone());
+            let mut buf = [0u8; size_of::<info_def::InfoSlot>()];
+            if cur.read(&mut buf).is_ok() {
+                f(&info_def::InfoSlot::from_bytes(&buf));
+            } else {
+                log::error!("迭代过程中读取时间槽数据失败: tsl={tsl:?}");
+            }
+            true
+        });
+    }
+
+    /// 返回温度高于 `threshold` 的所有记录（按时间从旧到新）
+    pub fn find_above_temp(&mut self, threshold: f32) -> Vec<info_def::InfoSlot> {
+        self.collect_where(|slot| slot.get_temperature() > threshold)
+    }
+
+    /// 返回温度不在 `[t_lo, t_hi]` 或湿度不在 `[h_lo, h_hi]` 范围内的所有记录（按时间从旧到新）
+    pub fn find_outside(&mut self, t_lo: f32, t_hi: f32, h_lo: f32, h_hi: f32) -> Vec<info_def::InfoSlot> {
+        self.collect_where(|slot| is_outside(slot, t_lo, t_hi, h_lo, h_hi))
+    }
+
+    /// 从最旧的记录开始遍历，在迭代过程中过滤，只保留满足条件的记录
+    fn collect_where(
+        &mut self,
+        mut predicate: impl FnMut(&info_def::InfoSlot) -> bool + Send,
+    ) -> Vec<info_def::InfoSlot> {
+        let mut result = Vec::new();
+        self.for_each_record(|slot| {
+            if predicate(slot) {
+                result.push(*slot);
+            }
+        });
+        result
+    }
+
+    /// 将时间范围 `[left, right]` 内的记录标记为删除
+    ///
+    /// 单条记录标记失败时记录日志并继续处理其余记录，全部处理完后返回错误
+    pub fn delete_range(&mut self, left: i64, right: i64) -> Result<()> {
+        self.used_bytes = None;
+        let mut deleted = 0;
+        let mut failed = 0;
+        self.db.tsdb_iter_by_time(left, right, |db, tsl| {
+            match db.set_status(tsl, flashdb_rs::TSLStatus::Deleted) {
+                Ok(_) => deleted += 1,
+                Err(e) => {
+                    log::error!("删除时间槽失败: tsl={tsl:?}, {e:?}");
+                    failed += 1;
+                }
+            }
+            true
+        });
+        log::info!("时间范围 [{left}, {right}] 内已删除 {deleted} 条记录");
+        if failed > 0 {
+            bail!("时间范围 [{left}, {right}] 内有 {failed} 条记录删除失败");
+        }
+        Ok(())
+    }
+
+    /// 将所有记录标记为删除
+    ///
+    /// 单条记录标记失败时记录日志并继续处理其余记录，全部处理完后返回错误
+    pub fn clear(&mut self) -> Result<()> {
+        self.used_bytes = None;
+        let mut deleted = 0;
+        let mut failed = 0;
+        self.db.tsdb_iter(|db, tsl| {
+            match db.set_status(tsl, flashdb_rs::TSLStatus::Deleted) {
+                Ok(_) => deleted += 1,
+                Err(e) => {
+                    log::error!("清空时间槽失败: tsl={tsl:?}, {e:?}");
+                    failed += 1;
+                }
+            }
+            true
+        }, false);
+        log::info!("已清空 {deleted} 条记录");
+        if failed > 0 {
+            bail!("清空数据库时有 {failed} 条记录删除失败");
+        }
+        Ok(())
+    }
 
     // pub fn get_all_data(&mut self) -> Vec<info_def::InfoSlot> {
     //     let mut result = Vec::new();
@@ -187,17 +846,809 @@ impl TimeDB {
     //     result
     // }
 
-    // pub fn earliest(&mut self) -> Option<info_def::InfoSlot> {
-    //     let mut tmp: Option<info_def::InfoSlot> = None;
-    //     self.db.tsdb_iter(|db, tsl| {
-    //         if let Ok(Some(data)) = db.get_value(tsl) {
-    //             tmp = Some(info_def::InfoSlot::from_bytes(data.as_slice()));
-    //         }
-    //         false
-    //     }, false);
-    //     tmp
-    // }
+}
+
+/// `TimeDB::insert` 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertOutcome {
+    /// 记录已写入
+    Stored,
+    /// 时间戳不晚于最新记录，未写入
+    SkippedDuplicate,
+}
+
+/// 数据库存储用量
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    /// 未删除记录占用的字节数
+    pub used_bytes: usize,
+    /// 数据库容量（字节）
+    pub capacity_bytes: usize,
+    /// 已用百分比 (0-100)，容量为 0 时为 0
+    pub pct: f32,
+}
+
+impl Usage {
+    fn new(used_bytes: usize, capacity_bytes: usize) -> Self {
+        let pct = if capacity_bytes == 0 {
+            0.0
+        } else {
+            (used_bytes as f32 / capacity_bytes as f32 * 100.0).min(100.0)
+        };
+        Self {
+            used_bytes,
+            capacity_bytes,
+            pct,
+        }
+    }
+}
+
+/// 存储健康状态汇总，见 `TimeDB::storage_report`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageReport {
+    /// 未删除的记录数
+    pub records: usize,
+    pub usage: Usage,
+    /// 最旧记录的时间戳，数据库为空时为 `None`
+    pub oldest_time: Option<i64>,
+    /// 最新记录的时间戳，数据库为空时为 `None`
+    pub newest_time: Option<i64>,
+    /// 估计的写入速率（条/小时），插入少于两条时为 `None`
+    pub insert_rate_per_hour: Option<f32>,
+    /// 预计达到容量警戒线的时间
+    pub time_to_full: Option<Duration>,
+    pub write_amplification: f32,
+    /// 最近一轮完整校验的结果，尚未完成过一轮时为 `None`
+    pub last_scrub: Option<ScrubReport>,
+}
+
+/// 一段时间内温湿度的统计结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    pub count: usize,
+    pub temp_min: f32,
+    pub temp_max: f32,
+    pub temp_avg: f32,
+    pub hum_min: f32,
+    pub hum_max: f32,
+    pub hum_avg: f32,
+}
+
+/// 逐条累计统计值，不保存记录本身
+struct AggregateAccumulator {
+    count: usize,
+    temp_min: f32,
+    temp_max: f32,
+    temp_sum: f32,
+    hum_min: f32,
+    hum_max: f32,
+    hum_sum: f32,
+    /// 已累计记录中最高的存储精度
+    precision: info_def::Precision,
+}
+
+impl Default for AggregateAccumulator {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            temp_min: f32::INFINITY,
+            temp_max: f32::NEG_INFINITY,
+            temp_sum: 0.0,
+            hum_min: f32::INFINITY,
+            hum_max: f32::NEG_INFINITY,
+            hum_sum: 0.0,
+            precision: info_def::Precision::Tenths,
+        }
+    }
+}
+
+impl AggregateAccumulator {
+    fn push(&mut self, slot: &info_def::InfoSlot) {
+        let temperature = slot.get_temperature();
+        let humidity = slot.get_humidity();
+        self.count += 1;
+        self.temp_min = self.temp_min.min(temperature);
+        self.temp_max = self.temp_max.max(temperature);
+        self.temp_sum += temperature;
+        self.hum_min = self.hum_min.min(humidity);
+        self.hum_max = self.hum_max.max(humidity);
+        self.hum_sum += humidity;
+        if slot.precision() == info_def::Precision::Hundredths {
+            self.precision = info_def::Precision::Hundredths;
+        }
+    }
+
+    fn finish(self) -> Option<Aggregate> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(Aggregate {
+            count: self.count,
+            temp_min: self.temp_min,
+            temp_max: self.temp_max,
+            temp_avg: self.temp_sum / self.count as f32,
+            hum_min: self.hum_min,
+            hum_max: self.hum_max,
+            hum_avg: self.hum_sum / self.count as f32,
+        })
+    }
+
+    /// 以平均值生成一条记录，精度取累计记录中最高的，避免高精度数据被截断为 0.1
+    fn finish_slot(self) -> Option<info_def::InfoSlot> {
+        let precision = self.precision;
+        self.finish().map(|aggregate| {
+            info_def::InfoSlot::new_with_precision(aggregate.temp_avg, aggregate.hum_avg, precision)
+        })
+    }
+}
+
+/// 计算时间戳 `time` 落在 `[left, right]` 等分后的第几个区间，超出范围时返回 `None`
+fn bucket_index(time: i64, left: i64, right: i64, buckets: usize) -> Option<usize> {
+    if buckets == 0 || time < left || time > right {
+        return None;
+    }
+    // 使用 i128 避免大时间跨度相乘溢出
+    let span = right as i128 - left as i128 + 1;
+    let index = (time as i128 - left as i128) * buckets as i128 / span;
+    Some(index as usize)
+}
+
+/// 按时间顺序逐个接收时间戳，相邻间隔超过 `max_delta` 时报告缺口
+struct GapDetector {
+    max_delta: i64,
+    prev: Option<i64>,
+}
+
+impl GapDetector {
+    fn new(max_delta: i64) -> Self {
+        Self { max_delta, prev: None }
+    }
+
+    fn push(&mut self, time: i64) -> Option<(i64, i64)> {
+        let gap = self
+            .prev
+            .filter(|&prev| time.saturating_sub(prev) > self.max_delta)
+            .map(|prev| (prev, time));
+        self.prev = Some(time);
+        gap
+    }
+}
+
+/// 一轮完整数据校验的结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScrubReport {
+    pub valid: usize,
+    pub corrupt: usize,
+}
+
+/// 增量校验的进度，记录上次校验到的时间戳和本轮的累计结果
+#[derive(Debug, Default)]
+struct Scrubber {
+    cursor: Option<i64>,
+    current: ScrubReport,
+    last_pass: Option<ScrubReport>,
+}
+
+impl Scrubber {
+    /// 下一批记录的起始时间戳（包含）
+    fn next_from(&self) -> i64 {
+        self.cursor.map_or(0, |time| time.saturating_add(1))
+    }
+
+    /// 累计一批校验结果；批次不满 `per_cycle` 条说明已到末尾，本轮结束并返回结果
+    fn advance(&mut self, batch: &[(i64, bool)], per_cycle: usize) -> Option<ScrubReport> {
+        for &(time, valid) in batch {
+            if valid {
+                self.current.valid += 1;
+            } else {
+                self.current.corrupt += 1;
+            }
+            self.cursor = Some(time);
+        }
+        if batch.len() >= per_cycle {
+            return None;
+        }
+        let report = std::mem::take(&mut self.current);
+        self.cursor = None;
+        self.last_pass = Some(report);
+        Some(report)
+    }
+}
+
+/// 记录的数值是否合理：温湿度为有限值且湿度在 0~100% 之间
+fn record_is_plausible(slot: &info_def::InfoSlot) -> bool {
+    let humidity = slot.get_humidity();
+    slot.get_temperature().is_finite() && (0.0..=100.0).contains(&humidity)
+}
+
+/// 在 `timestamp` 两侧的候选记录中选出时间差最小的一条，距离相等时取较早的 `before`
+fn nearest<T>(timestamp: i64, before: Option<(i64, T)>, after: Option<(i64, T)>) -> Option<(i64, T)> {
+    match (before, after) {
+        (Some(b), Some(a)) => {
+            let distance = |time: i64| (time as i128 - timestamp as i128).unsigned_abs();
+            if distance(a.0) < distance(b.0) {
+                Some(a)
+            } else {
+                Some(b)
+            }
+        }
+        (before, after) => before.or(after),
+    }
+}
+
+/// 按顺序筛选出时间戳严格递增且晚于 `last_time` 的记录
+fn increasing_after<T: Copy>(last_time: i64, records: &[(i64, T)]) -> Vec<(i64, T)> {
+    let mut last = last_time;
+    records
+        .iter()
+        .filter(|&&(time, _)| {
+            let accepted = time > last;
+            if accepted {
+                last = time;
+            }
+            accepted
+        })
+        .copied()
+        .collect()
+}
+
+/// 返回出现不止一次的时间戳，结果升序且去重
+fn duplicate_timestamps(mut timestamps: Vec<i64>) -> Vec<i64> {
+    timestamps.sort_unstable();
+    let mut duplicates: Vec<i64> = timestamps
+        .windows(2)
+        .filter(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+        .collect();
+    duplicates.dedup();
+    duplicates
+}
+
+/// 将一条记录以 JSON 对象格式追加到 `out`，小数位数与记录精度一致
+fn write_json_record(out: &mut String, time: i64, slot: &info_def::InfoSlot) {
+    use std::fmt::Write;
+
+    // 写入 String 不会失败
+    let _ = write!(out, r#"{{"time":{time},"#);
+    slot.write_json_fields(out);
+    out.push('}');
+}
+
+/// CSV 导出的表头
+const CSV_HEADER: &str = "timestamp,temperature_c,humidity_pct\n";
+
+/// 将一条记录以 CSV 行格式写入 `writer`，温湿度保留一位小数
+fn write_csv_row<W: std::io::Write>(
+    writer: &mut W,
+    time: i64,
+    slot: &info_def::InfoSlot,
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "{time},{:.1},{:.1}",
+        slot.get_temperature(),
+        slot.get_humidity()
+    )
+}
+
+/// 写入速率 EWMA 的平滑系数，越大越偏向最近的间隔
+const INSERT_RATE_ALPHA: f32 = 0.2;
+
+/// 按剩余记录数和写入速率计算写满所需时间，速率无效或不大于 0 时返回 `None`
+fn time_to_fill(remaining_records: usize, rate_per_hour: f32) -> Option<Duration> {
+    if !rate_per_hour.is_finite() || rate_per_hour <= 0.0 {
+        return None;
+    }
+    let hours = remaining_records as f32 / rate_per_hour;
+    // 速率极小时结果超出 Duration 的范围（甚至为 inf），饱和为 Duration::MAX
+    Some(Duration::try_from_secs_f32(hours * 3600.0).unwrap_or(Duration::MAX))
+}
+
+/// 用插入时间戳间隔的指数加权移动平均（EWMA）估计写入速率
+struct RateEstimator {
+    alpha: f32,
+    last_time: Option<i64>,
+    /// 平滑后的插入间隔，单位与存储时间戳相同（秒或毫秒，见 `TimeUnit`）
+    interval_ticks: Option<f32>,
+}
 
+impl RateEstimator {
+    fn new(alpha: f32) -> Self {
+        Self {
+            alpha,
+            last_time: None,
+            interval_ticks: None,
+        }
+    }
+
+    fn record(&mut self, time: i64) {
+        if let Some(last) = self.last_time {
+            let delta = (time - last).max(0) as f32;
+            self.interval_ticks = Some(match self.interval_ticks {
+                Some(avg) => avg + self.alpha * (delta - avg),
+                None => delta,
+            });
+        }
+        self.last_time = Some(time);
+    }
+
+    /// 每 3600 个时间戳单位的插入条数，时间戳单位为秒时即每小时条数
+    fn per_hour(&self) -> Option<f32> {
+        self.interval_ticks
+            .filter(|&interval| interval > 0.0)
+            .map(|interval| 3600.0 / interval)
+    }
 }
 
+/// 计算写入前需要清理的字节数，已用容量加上待写入的 `pending_size` 未达到警戒线时返回 `None`
+///
+/// 达到警戒线时清理最大容量的 10%，批量写入时至少腾出整批记录的空间
+fn cleanup_plan(
+    used_size: usize,
+    pending_size: usize,
+    max_size: usize,
+    threshold_pct: f32,
+) -> Option<usize> {
+    let threshold_size = (max_size as f32 * threshold_pct / 100.0) as usize;
+    if used_size.saturating_add(pending_size) < threshold_size {
+        return None;
+    }
+    Some(((max_size as f32 * 0.1) as usize).max(pending_size))
+}
+
+/// 将容量警戒线限制在 `[1.0, 100.0]`，NaN 视为最小值
+fn clamp_threshold(pct: f32) -> f32 {
+    pct.max(1.0).min(100.0)
+}
+
+/// 计算写放大系数，逻辑写入为 0 时返回 0
+fn write_amplification(logical_bytes: usize, physical_written: usize, physical_erased: usize) -> f32 {
+    if logical_bytes == 0 {
+        return 0.0;
+    }
+    (physical_written + physical_erased) as f32 / logical_bytes as f32
+}
+
+/// 带上限的结果收集器，超出上限时记录截断标志而不是继续分配
+struct BoundedCollector<T> {
+    items: Vec<T>,
+    max: usize,
+    truncated: bool,
+}
+
+impl<T> BoundedCollector<T> {
+    fn new(max: usize) -> Self {
+        Self {
+            items: Vec::new(),
+            max,
+            truncated: false,
+        }
+    }
 
+    fn is_full(&self) -> bool {
+        self.items.len() >= self.max
+    }
+
+    fn mark_truncated(&mut self) {
+        self.truncated = true;
+    }
+
+    fn push(&mut self, item: T) {
+        self.items.push(item);
+    }
+}
+
+/// 带上限的 JSON 数组写入器，与 `BoundedCollector` 相同，超出上限时只记录截断标志
+struct BoundedJsonArray {
+    out: String,
+    count: usize,
+    max: usize,
+    truncated: bool,
+}
+
+impl BoundedJsonArray {
+    fn new(max: usize) -> Self {
+        Self {
+            out: String::from("["),
+            count: 0,
+            max,
+            truncated: false,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.count >= self.max
+    }
+
+    fn mark_truncated(&mut self) {
+        self.truncated = true;
+    }
+
+    fn push(&mut self, time: i64, slot: &info_def::InfoSlot) {
+        if self.count > 0 {
+            self.out.push(',');
+        }
+        write_json_record(&mut self.out, time, slot);
+        self.count += 1;
+    }
+
+    /// 闭合数组，返回 JSON 字符串和截断标志
+    fn finish(mut self) -> (String, bool) {
+        self.out.push(']');
+        (self.out, self.truncated)
+    }
+}
+
+/// 温度不在 `[t_lo, t_hi]` 或湿度不在 `[h_lo, h_hi]` 范围内
+fn is_outside(slot: &info_def::InfoSlot, t_lo: f32, t_hi: f32, h_lo: f32, h_hi: f32) -> bool {
+    let temperature = slot.get_temperature();
+    let humidity = slot.get_humidity();
+    temperature < t_lo || temperature > t_hi || humidity < h_lo || humidity > h_hi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use info_def::InfoSlot;
+
+    #[test]
+    fn test_is_outside_mixed_dataset() {
+        let dataset = [
+            InfoSlot::new_from_f32(22.0, 50.0), // 正常
+            InfoSlot::new_from_f32(31.5, 50.0), // 温度过高
+            InfoSlot::new_from_f32(22.0, 85.0), // 湿度过高
+            InfoSlot::new_from_f32(-2.0, 40.0), // 温度过低
+            InfoSlot::new_from_f32(30.0, 70.0), // 恰好在边界上
+        ];
+
+        let outside: Vec<_> = dataset
+            .iter()
+            .filter(|slot| is_outside(slot, 0.0, 30.0, 20.0, 70.0))
+            .copied()
+            .collect();
+        assert_eq!(outside, vec![dataset[1], dataset[2], dataset[3]]);
+
+        let above: Vec<_> = dataset
+            .iter()
+            .filter(|slot| slot.get_temperature() > 30.0)
+            .copied()
+            .collect();
+        assert_eq!(above, vec![dataset[1]]);
+    }
+
+    #[test]
+    fn test_aggregate_accumulator() {
+        assert_eq!(AggregateAccumulator::default().finish(), None);
+
+        let mut acc = AggregateAccumulator::default();
+        for slot in [
+            InfoSlot::new_from_f32(20.0, 40.0),
+            InfoSlot::new_from_f32(25.0, 60.0),
+            InfoSlot::new_from_f32(-3.0, 50.0),
+        ] {
+            acc.push(&slot);
+        }
+        let aggregate = acc.finish().unwrap();
+        assert_eq!(aggregate.count, 3);
+        assert_eq!(aggregate.temp_min, -3.0);
+        assert_eq!(aggregate.temp_max, 25.0);
+        assert!((aggregate.temp_avg - 14.0).abs() < 1e-4);
+        assert_eq!(aggregate.hum_min, 40.0);
+        assert_eq!(aggregate.hum_max, 60.0);
+        assert!((aggregate.hum_avg - 50.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_aggregate_slot_keeps_finest_precision() {
+        let mut acc = AggregateAccumulator::default();
+        acc.push(&InfoSlot::new_from_f32(20.0, 40.0));
+        acc.push(&InfoSlot::new_with_precision(20.02, 40.04, info_def::Precision::Hundredths));
+        let slot = acc.finish_slot().unwrap();
+        assert_eq!(slot.precision(), info_def::Precision::Hundredths);
+        assert!((slot.get_temperature() - 20.01).abs() < 1e-4);
+        assert!((slot.get_humidity() - 40.02).abs() < 1e-4);
+
+        let mut acc = AggregateAccumulator::default();
+        acc.push(&InfoSlot::new_from_f32(20.0, 40.0));
+        assert_eq!(acc.finish_slot().unwrap().precision(), info_def::Precision::Tenths);
+    }
+
+    #[test]
+    fn test_bucket_index() {
+        // [0, 99] 分为 4 个区间，每个区间 25 个时间单位
+        assert_eq!(bucket_index(0, 0, 99, 4), Some(0));
+        assert_eq!(bucket_index(24, 0, 99, 4), Some(0));
+        assert_eq!(bucket_index(25, 0, 99, 4), Some(1));
+        assert_eq!(bucket_index(99, 0, 99, 4), Some(3));
+        assert_eq!(bucket_index(100, 0, 99, 4), None);
+        assert_eq!(bucket_index(-1, 0, 99, 4), None);
+        assert_eq!(bucket_index(5, 0, 99, 0), None);
+
+        // 区间数多于时间点时不会越界
+        assert_eq!(bucket_index(9, 0, 9, 128), Some(115));
+        assert_eq!(bucket_index(i64::MAX, i64::MIN, i64::MAX, 128), Some(127));
+    }
+
+    #[test]
+    fn test_gap_detector() {
+        // 期望间隔 10，容差 2：间隔大于 12 才算缺口
+        let mut detector = GapDetector::new(10 + 2);
+        let gaps: Vec<_> = [0, 10, 22, 40, 50, 80]
+            .into_iter()
+            .filter_map(|t| detector.push(t))
+            .collect();
+        assert_eq!(gaps, vec![(22, 40), (50, 80)]);
+
+        // 少于两条记录时没有缺口
+        let mut detector = GapDetector::new(12);
+        assert_eq!(detector.push(100), None);
+    }
+
+    #[test]
+    fn test_duplicate_timestamps() {
+        assert!(duplicate_timestamps(vec![]).is_empty());
+        assert!(duplicate_timestamps(vec![1, 2, 3]).is_empty());
+
+        // 同一时间戳出现三次只报告一次，乱序输入也能检测
+        let duplicates = duplicate_timestamps(vec![30, 10, 20, 10, 30, 10, 40]);
+        assert_eq!(duplicates, vec![10, 30]);
+    }
+
+    #[test]
+    fn test_scrubber_full_pass() {
+        // 10 条记录，时间戳 101..=110，其中一条损坏
+        let records: Vec<(i64, bool)> = (101..=110).map(|t| (t, t != 105)).collect();
+        let mut scrubber = Scrubber::default();
+        let step = |scrubber: &mut Scrubber, per_cycle: usize| {
+            let from = scrubber.next_from();
+            let batch: Vec<_> = records
+                .iter()
+                .copied()
+                .filter(|&(t, _)| t >= from)
+                .take(per_cycle)
+                .collect();
+            scrubber.advance(&batch, per_cycle)
+        };
+
+        // 每次 3 条：3 + 3 + 3 + 1，第 4 次完成一轮
+        for _ in 0..3 {
+            assert_eq!(step(&mut scrubber, 3), None);
+        }
+        let report = step(&mut scrubber, 3).unwrap();
+        assert_eq!(report, ScrubReport { valid: 9, corrupt: 1 });
+        assert_eq!(scrubber.last_pass, Some(report));
+
+        // 记录数恰好是整数倍时需要多一次空批次才能确认到达末尾
+        for _ in 0..2 {
+            assert_eq!(step(&mut scrubber, 5), None);
+        }
+        assert_eq!(step(&mut scrubber, 5), Some(report));
+    }
+
+    #[test]
+    fn test_increasing_after() {
+        let records: Vec<(i64, u8)> = (1..=100).map(|i| (1000 + i, i as u8)).collect();
+        assert_eq!(increasing_after(1000, &records).len(), 100);
+        // 不晚于最新记录的部分被跳过
+        assert_eq!(increasing_after(1050, &records).len(), 50);
+
+        // 批次内乱序或重复的时间戳被跳过
+        let records = [(10, 'a'), (12, 'b'), (12, 'c'), (11, 'd'), (15, 'e')];
+        assert_eq!(increasing_after(0, &records), vec![(10, 'a'), (12, 'b'), (15, 'e')]);
+        assert!(increasing_after(20, &records).is_empty());
+    }
+
+    #[test]
+    fn test_usage() {
+        let usage = Usage::new(1024, 4096);
+        assert_eq!(usage.pct, 25.0);
+        assert_eq!(Usage::new(0, 0).pct, 0.0);
+        assert_eq!(Usage::new(5000, 4096).pct, 100.0);
+    }
+
+    #[test]
+    fn test_nearest() {
+        assert_eq!(nearest::<()>(100, None, None), None);
+        assert_eq!(nearest(100, Some((90, 'b')), None), Some((90, 'b')));
+        assert_eq!(nearest(100, None, Some((130, 'a'))), Some((130, 'a')));
+
+        assert_eq!(nearest(100, Some((90, 'b')), Some((130, 'a'))), Some((90, 'b')));
+        assert_eq!(nearest(100, Some((60, 'b')), Some((101, 'a'))), Some((101, 'a')));
+        // 距离相等时取较早的记录
+        assert_eq!(nearest(100, Some((95, 'b')), Some((105, 'a'))), Some((95, 'b')));
+        // 极端时间戳不会溢出
+        assert_eq!(nearest(0, Some((i64::MIN, 'b')), Some((i64::MAX, 'a'))), Some((i64::MAX, 'a')));
+    }
+
+    #[test]
+    fn test_write_json_record() {
+        let mut out = String::new();
+        write_json_record(&mut out, 1_700_000_000, &InfoSlot::new_from_f32(25.3, 60.0));
+        assert_eq!(out, r#"{"time":1700000000,"temperature":25.3,"humidity":60.0}"#);
+
+        out.clear();
+        write_json_record(&mut out, -1, &InfoSlot::new_from_f32(-5.0, 0.0));
+        assert_eq!(out, r#"{"time":-1,"temperature":-5.0,"humidity":0.0}"#);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend_from_slice(CSV_HEADER.as_bytes());
+        write_csv_row(&mut out, 1_700_000_000, &InfoSlot::new_from_f32(25.34, 60.0)).unwrap();
+        write_csv_row(&mut out, 1_700_000_060, &InfoSlot::new_from_f32(-5.0, 0.0)).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "timestamp,temperature_c,humidity_pct");
+        assert_eq!(lines[1], "1700000000,25.3,60.0");
+        assert_eq!(lines[2], "1700000060,-5.0,0.0");
+    }
+
+    #[test]
+    fn test_db_name_validation() {
+        assert_eq!(validate_db_name("temperature_db"), Ok(()));
+        assert_eq!(validate_db_name(&"a".repeat(MAX_DB_NAME_LEN)), Ok(()));
+
+        assert_eq!(validate_db_name(""), Err(DbNameError::Empty));
+        let overlong = "a".repeat(MAX_DB_NAME_LEN + 1);
+        let err = validate_db_name(&overlong).unwrap_err();
+        assert_eq!(err, DbNameError::TooLong { len: 65 });
+        assert_eq!(err.to_string(), "数据库名称过长: 65 字节，最多 64 字节");
+        assert_eq!(
+            validate_db_name("temp db"),
+            Err(DbNameError::InvalidChar { ch: ' ', index: 4 })
+        );
+        assert_eq!(
+            validate_db_name("温度"),
+            Err(DbNameError::InvalidChar { ch: '温', index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_sanitize_db_name() {
+        assert_eq!(sanitize_db_name("temperature_db"), "temperature_db");
+        assert_eq!(sanitize_db_name("temp db/1"), "temp_db_1");
+        assert_eq!(sanitize_db_name(""), "db");
+
+        let repaired = sanitize_db_name(&"温".repeat(100));
+        assert_eq!(repaired.len(), MAX_DB_NAME_LEN);
+        assert_eq!(validate_db_name(&repaired), Ok(()));
+    }
+
+    #[test]
+    fn test_time_to_fill() {
+        // 1200 条剩余，每小时 12 条 -> 100 小时
+        assert_eq!(time_to_fill(1200, 12.0), Some(Duration::from_secs(100 * 3600)));
+        // 每分钟一条 -> 60 条/小时，720 条剩余 -> 12 小时
+        assert_eq!(time_to_fill(720, 60.0), Some(Duration::from_secs(12 * 3600)));
+        // 已达到警戒线
+        assert_eq!(time_to_fill(0, 60.0), Some(Duration::ZERO));
+
+        // 没有写入时永远不会写满
+        assert_eq!(time_to_fill(100, 0.0), None);
+        assert_eq!(time_to_fill(100, -1.0), None);
+        assert_eq!(time_to_fill(100, f32::NAN), None);
+
+        // 速率极小时饱和，而不是 panic
+        assert_eq!(time_to_fill(100, 1e-35), Some(Duration::MAX));
+        assert_eq!(time_to_fill(usize::MAX, f32::MIN_POSITIVE), Some(Duration::MAX));
+    }
+
+    #[test]
+    fn test_rate_estimator() {
+        let mut rate = RateEstimator::new(0.5);
+        assert_eq!(rate.per_hour(), None);
+
+        rate.record(0);
+        assert_eq!(rate.per_hour(), None);
+
+        // 间隔 60 秒 -> 60 条/小时
+        rate.record(60);
+        assert_eq!(rate.per_hour(), Some(60.0));
+
+        // 间隔变为 120 秒，EWMA 间隔为 90 秒 -> 40 条/小时
+        rate.record(180);
+        assert_eq!(rate.per_hour(), Some(40.0));
+    }
+
+    #[test]
+    fn test_capacity_threshold_clamping() {
+        assert_eq!(clamp_threshold(-5.0), 1.0);
+        assert_eq!(clamp_threshold(0.5), 1.0);
+        assert_eq!(clamp_threshold(150.0), 100.0);
+        assert_eq!(clamp_threshold(80.0), 80.0);
+        assert_eq!(clamp_threshold(f32::NAN), 1.0);
+    }
+
+    #[test]
+    fn test_cleanup_plan() {
+        let (max_size, slot_size) = (40_960, 8);
+        // 警戒线 80% 为 32768B
+        assert_eq!(cleanup_plan(30_000, 0, max_size, 80.0), None);
+        assert_eq!(cleanup_plan(32_768, 0, max_size, 80.0), Some(4_096));
+
+        // 批量写入使总量越过警戒线，至少腾出整批记录的空间
+        let pending = 1_000;
+        let freed = cleanup_plan(30_000, pending * slot_size, max_size, 80.0).unwrap();
+        assert!(freed >= pending * slot_size);
+    }
+
+    #[test]
+    fn test_write_amplification_with_cleanup() {
+        let record = size_of::<InfoSlot>();
+        // 假设每次插入写入记录本身和一个 8 字节的索引
+        let per_insert = record + 8;
+
+        // 10 次插入，没有擦除
+        let logical = 10 * record;
+        let written = 10 * per_insert;
+        let expected = per_insert as f32 / record as f32;
+        assert_eq!(write_amplification(logical, written, 0), expected);
+
+        // 清理时擦除一个 4096 字节的扇区
+        let amplified = write_amplification(logical, written, 4096);
+        assert_eq!(amplified, (written + 4096) as f32 / logical as f32);
+        assert!(amplified > expected);
+
+        // 尚未写入时不产生 NaN
+        assert_eq!(write_amplification(0, 0, 4096), 0.0);
+    }
+
+    // 模拟 tsdb_iter_by_time 的回调流程：回调返回 false 时停止迭代
+    fn collect_bounded(records: &[i32], max: usize) -> (Vec<i32>, bool, usize) {
+        let mut collector = BoundedCollector::new(max);
+        let mut visited = 0;
+        for &record in records {
+            visited += 1;
+            if collector.is_full() {
+                collector.mark_truncated();
+                break;
+            }
+            collector.push(record);
+        }
+        (collector.items, collector.truncated, visited)
+    }
+
+    #[test]
+    fn test_bounded_collector_truncates() {
+        let (items, truncated, visited) = collect_bounded(&[1, 2, 3, 4, 5], 2);
+        assert_eq!(items, vec![1, 2]);
+        assert!(truncated);
+        // 达到上限后只多看一条记录就停止
+        assert_eq!(visited, 3);
+    }
+
+    #[test]
+    fn test_bounded_json_array_truncates() {
+        let slot = InfoSlot::new_from_f32(25.0, 50.0);
+        let mut array = BoundedJsonArray::new(2);
+        for time in 1..=3 {
+            if array.is_full() {
+                array.mark_truncated();
+                break;
+            }
+            array.push(time, &slot);
+        }
+        let (out, truncated) = array.finish();
+        assert!(truncated);
+        assert_eq!(out.matches("\"time\"").count(), 2);
+        assert!(out.starts_with(r#"[{"time":1,"#) && out.ends_with("}]"));
+
+        let (out, truncated) = BoundedJsonArray::new(0).finish();
+        assert_eq!(out, "[]");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn test_bounded_collector_exact_fit() {
+        let (items, truncated, _) = collect_bounded(&[1, 2, 3], 3);
+        assert_eq!(items, vec![1, 2, 3]);
+        assert!(!truncated);
+
+        let (items, truncated, _) = collect_bounded(&[], 0);
+        assert!(items.is_empty());
+        assert!(!truncated);
+    }
+}
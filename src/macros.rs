@@ -6,8 +6,8 @@
 /// 
 /// 这个宏简化了从 GPIO 管理器获取配置的过程。
 /// 返回一个元组 `(peripherals, gpio_config)`，其中：
-/// - `peripherals`: 完整的 `Peripherals` 对象，用于访问 modem、SPI2 等其他外设
-/// - `gpio_config`: `GPIOConfig` 对象，包含所有已配置的 GPIO 引脚
+/// - `peripherals`: 完整的 `Peripherals` 对象，用于访问 modem 等其他外设
+/// - `gpio_config`: `GPIOConfig` 对象，包含所有已配置的 GPIO 引脚和 SPI2/I2C0 外设
 /// 
 /// # 示例
 /// ```
@@ -5,13 +5,26 @@ mod peripherals;
 mod service;
 mod utils;
 
+use service::alarm::{AlarmEvent, AlarmMonitor};
+use service::http::HttpServerBuilder;
+use service::mqtt::{Command, MqttPublisher};
 use service::ntp;
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::data::display_pipeline::DisplayPipeline;
+use crate::data::rtc_window::RtcBackedQueue;
+use crate::data::snapshot::Snapshot;
+use crate::data::time_db::InsertOutcome;
+use crate::peripherals::indicator::Indicator;
+use crate::peripherals::power;
 use crate::peripherals::screen::{self, ScreenBuilder};
+#[cfg(feature = "ds18b20")]
+use crate::peripherals::ds18b20::Ds18b20Sensor;
+#[cfg(not(feature = "ds18b20"))]
 use crate::peripherals::temperature_sensor::TemperatureSensor;
-use crate::peripherals::wifi::WifiBuilder;
+use crate::peripherals::wifi::{LivenessConfig, LivenessMonitor, WifiBuilder};
 // use embedded_hal::digital::{InputPin, OutputPin, PinState};
 
 include!("../.env/config.rs");
@@ -23,69 +36,142 @@ fn main() -> anyhow::Result<()> {
 
     // Bind the log crate to the ESP Logging facilities
     esp_idf_svc::log::EspLogger::initialize_default();
+    let boot = Instant::now();
 
     // 使用配置系统获取外设
     let (peripherals, gpio_config) = configure_peripherals!();
 
+    // DHT22 上电后需要预热，连接 WiFi 等初始化的耗时计入预热时间
+    let sensor_warmup = power::SensorWarmup::start(power::DHT22_WARMUP);
+    // 从深度睡眠唤醒时 RTC 仍在计时，睡眠前已同步过的系统时间可以直接使用
+    let rtc_time_valid = power::rtc_time_valid();
+    if rtc_time_valid {
+        log::info!("从深度睡眠唤醒，使用 RTC 维护的系统时间");
+    }
+
     // let mut random_generator = utils::rand::RandomGenerator::new();
-    let mut time_db = data::time_db::TimeDB::new("temperature_db", 4096 * 5, true)?;
+    // HTTP 服务在独立任务中读取数据库，因此放在 Mutex 中共享
+    let time_db = Arc::new(Mutex::new(data::time_db::TimeDB::new("temperature_db", 4096 * 5, true)?));
 
     // wifi 连接
     let wifi_buider = WifiBuilder::new(WIFI_SSID, WIFI_PASSWORD);
     let sysloop = esp_idf_svc::eventloop::EspSystemEventLoop::take()?;
 
-    let wifi = wifi_buider.build(peripherals.modem, sysloop)?;
+    let mut wifi = wifi_buider.build(peripherals.modem, sysloop)?;
     log::info!("WiFi 已连接, IP 地址: {:?}", wifi.get_configuration());
 
-    // 等待网络完全就绪
-    log::info!("等待网络稳定...");
-    sleep(Duration::from_secs(2));
+    // 主循环每个周期发布一次状态快照，HTTP 服务的 `/` 返回最新的快照
+    let latest_snapshot = Arc::new(Mutex::new(None));
+    // HTTP 查询服务句柄需要在整个程序运行期间保持存活
+    let _http_server = config::HTTP_SERVER_PORT.and_then(|port| {
+        HttpServerBuilder::new()
+            .port(port)
+            .snapshot(latest_snapshot.clone())
+            .start(time_db.clone())
+            .inspect_err(|e| log::error!("HTTP 服务启动失败: {e:?}"))
+            .ok()
+    });
+
+    // 连接在后台建立，broker 不可达时不影响主循环
+    let mut mqtt_publisher = config::MQTT_BROKER_URL.and_then(|url| {
+        match config::MQTT_COMMAND_TOPIC {
+            Some(command_topic) => MqttPublisher::with_commands(url, config::MQTT_TOPIC, command_topic),
+            None => MqttPublisher::new(url, config::MQTT_TOPIC),
+        }
+        .inspect_err(|e| log::error!("MQTT 客户端创建失败: {e:?}"))
+        .ok()
+    });
 
-    // 测试网络连接
-    if !ntp::test_network_connectivity() {
+    // 测试网络连接，接口刚就绪时可能失败，按指数退避重试
+    // NTP 句柄需要在整个程序运行期间保持存活，否则 SNTP 服务会被停止
+    let ntp_handle = if !ntp::wait_for_network(5, Duration::from_secs(1)) {
         log::error!("网络连接不可用，跳过 NTP 同步");
         // 继续运行，但不同步时间
+        None
     } else {
         // 尝试同步时间
         log::info!("开始 NTP 时间同步...");
         let ntp_res = ntp::NtpConfig::new()
             .china_servers()
             .timeout(30) // 增加超时时间到 30 秒
-            .wait_for_sync(true)
+            .wait_for_sync(!rtc_time_valid) // RTC 时间有效时在后台同步，不阻塞采样
+            .resync_interval(Duration::from_secs(6 * 3600)) // 每 6 小时重新同步一次
             .init();
 
         match ntp_res {
-            Ok(_sntp) => {
+            Ok((handle, ntp::NtpSyncOutcome::Completed { .. } | ntp::NtpSyncOutcome::Converging { .. })) => {
                 log::info!("✅ NTP 时间同步成功");
+                Some(handle)
+            }
+            Ok((handle, outcome)) => {
+                log::warn!("⚠️  NTP 尚未完成同步 ({outcome:?})，同步完成前不会写入数据库");
+                Some(handle)
             }
             Err(e) => {
                 log::warn!("⚠️  NTP 时间同步失败: {e:?}，程序将继续运行");
                 log::info!("💡 提示：可以尝试使用全局 NTP 服务器");
+                None
             }
         }
-    }
+    };
 
+    // 启用 ds18b20 feature 时使用 DS18B20 探头（只测温度），否则使用 DHT22
+    #[cfg(feature = "ds18b20")]
+    let mut temperature_sensor = Ds18b20Sensor::from_pin(gpio_config.temperature_pin)?;
+    #[cfg(not(feature = "ds18b20"))]
     let mut temperature_sensor = TemperatureSensor::from_pin(gpio_config.temperature_pin)?;
 
-    // 使用 ScreenBuilder 创建屏幕实例
-    let mut screen = ScreenBuilder::with_pins(
-        peripherals.spi2,
-        gpio_config.spi_sck,  // SCK
-        gpio_config.spi_mosi, // MOSI
+    // 使用 ScreenBuilder 创建屏幕实例，自动检测 I2C/SPI 接口
+    let (mut screen, _screen_interface) = ScreenBuilder::auto_detect(
+        gpio_config.i2c0,
+        gpio_config.spi2,
+        gpio_config.spi_sck,  // SCK / SCL
+        gpio_config.spi_mosi, // MOSI / SDA
         gpio_config.spi_cs,   // CS
         gpio_config.spi_dc,   // DC
     )?;
 
-    let mut cnt = 10;
+    // 定期 ping 网关，连续失败时强制重连 WiFi
+    let mut wifi_liveness = LivenessMonitor::new(LivenessConfig::default());
+
+    #[cfg(feature = "fault-inject")]
+    utils::fault_inject::spawn_console()?;
+
+    // 显示前对最近 5 次读数做滑动平均，抑制 DHT22 的读数抖动
+    // 低功耗模式下每次唤醒都从头启动，窗口保存在 RTC 内存中才能接着之前的读数平均
+    let rtc_window = config::DEEP_SLEEP_INTERVAL.and_then(|_| RtcBackedQueue::take());
+    let mut display_pipeline = match rtc_window {
+        Some(rtc) => DisplayPipeline::new().smooth_with_rtc(5, rtc),
+        None => DisplayPipeline::new().smooth(5),
+    };
+    // DS18B20 不测湿度，湿度固定为 0%，不检查湿度阈值
+    #[cfg(feature = "ds18b20")]
+    let mut alarm_monitor = AlarmMonitor::new(config::ALARM_CONFIG.without_humidity());
+    #[cfg(not(feature = "ds18b20"))]
+    let mut alarm_monitor = AlarmMonitor::new(config::ALARM_CONFIG);
+
+    // 采样间隔可以通过 MQTT 命令修改
+    let mut monitor = config::MONITOR_CONFIG;
+    // 配置了指示器引脚时，报警期间指示器闪烁，每次刷新屏幕时切换一次
+    let mut indicator = gpio_config.indicator_pin.map(Indicator::new).transpose()?;
     loop {
         log::info!("主循环: 读取传感器数据并打印");
+        wifi_liveness.check(&mut wifi);
+
         // let info_slot = random_generator.get_info_slot();
 
-        let info_slot = match temperature_sensor.read_data() {
+        sensor_warmup.wait_ready();
+        #[cfg(feature = "ds18b20")]
+        let reading = temperature_sensor.read_data();
+        // DHT22 两次读取之间至少间隔 2 秒
+        #[cfg(not(feature = "ds18b20"))]
+        let reading = temperature_sensor.read_data_retry(3, Duration::from_secs(2));
+
+        let info_slot = match reading {
             Ok(slot) => slot,
             Err(e) => {
                 log::error!("读取传感器数据失败: {e}");
-                sleep(Duration::from_secs(5));
+                sleep(monitor.sample_interval);
                 continue;
             }
         };
@@ -100,28 +186,67 @@ fn main() -> anyhow::Result<()> {
         };
 
         println!("读取到传感器数据: {info_slot}");
-        if time_db.insert(time, &info_slot).is_ok() {
-            log::info!("已将数据存入数据库");
+        for event in alarm_monitor.evaluate(&info_slot) {
+            log::warn!("{event}");
+            if let (AlarmEvent::Raised { .. }, Some(indicator)) = (event, indicator.as_mut()) {
+                indicator.alternate()?;
+            }
+        }
+        if let Some(indicator) = indicator.as_mut() {
+            if !alarm_monitor.is_alarming() && indicator.is_active() {
+                indicator.off()?;
+            }
+        }
+        // 时间未同步时时间戳接近 1970 年，不写入数据库
+        let time_synced = rtc_time_valid || ntp_handle.as_ref().is_some_and(|h| h.is_synced());
+        if time_synced {
+            power::mark_rtc_time_valid();
+        }
+        if !time_synced {
+            log::warn!("时间尚未同步，跳过写入数据库");
         } else {
-            log::error!("将数据存入数据库失败");
+            match time_db.lock().unwrap().insert(time, &info_slot) {
+                Ok(InsertOutcome::Stored) => log::info!("已将数据存入数据库"),
+                Ok(InsertOutcome::SkippedDuplicate) => {
+                    log::info!("时间戳与最新记录重复，本次读数未存入数据库")
+                }
+                Err(e) => log::error!("将数据存入数据库失败: {e}"),
+            }
+        }
+        if let Some(publisher) = mqtt_publisher.as_mut() {
+            publisher.publish(time_synced.then_some(time), &info_slot);
         }
 
-        // 使用英文绘制温度与湿度
-        let temp_hum_str = format!(
-            "TEMP:{:.1}°C\nHUMD:{:.1} %",
-            info_slot.get_temperature(),
-            info_slot.get_humidity()
+        // 增量校验数据库记录，完成一轮后打印汇总
+        time_db.lock().unwrap().scrub_step(config::SCRUB_RECORDS_PER_CYCLE);
+
+        let snapshot = Snapshot::collect(
+            Some(info_slot),
+            time_synced.then_some(time),
+            &time_db.lock().unwrap(),
+            &wifi,
+            boot,
+            time_synced,
         );
+        log::debug!("状态快照: {}", snapshot.to_json());
+        *latest_snapshot.lock().unwrap() = Some(snapshot.clone());
+
+        // 数据库保存原始读数，屏幕显示经过管道处理后的读数（使用英文）
+        let temp_hum_str = display_pipeline.apply(&info_slot).to_string();
         let temp_hum_pos = screen::to_point(15, 30);
 
-        // 实时显示秒数更新，每秒刷新一次屏幕，5秒后再读取新数据
-        for _ in 0..5 {
-            // 使用 utils::time 格式化本地时间（东八区为 8*3600）
-            let datetime_str = utils::time::get_formatted_time(
+        // 实时显示秒数更新，按 display_refresh 刷新屏幕，经过 sample_interval 后再读取新数据
+        for _ in 0..monitor.refreshes_per_sample() {
+            // 使用 utils::time 按配置的时区格式化本地时间
+            let datetime_str = utils::time::format_now(
+                config::TIMEZONE,
                 "[year]-[month]-[day] [hour]:[minute]:[second]",
-                8 * 3600,
             )
-            .unwrap_or_else(|| "<时间格式化失败>".to_string());
+            .unwrap_or_else(|e| {
+                log::warn!("时间格式化失败: {e}");
+                // 下面按字节截取，占位符只能使用 ASCII 字符
+                "0000-00-00 --:--:--".to_string()
+            });
 
             // 绘制时间
             screen.clear()?;
@@ -130,28 +255,49 @@ fn main() -> anyhow::Result<()> {
 
             // 绘制温度与湿度
             screen.draw_text_big(&temp_hum_str, temp_hum_pos)?;
-            screen.flush()?;
+            snapshot.draw(&mut screen)?;
+            // 使用 DMA 总线时不阻塞等待传输完成
+            screen.flush_start()?;
 
-            sleep(Duration::from_millis(1000));
+            if let Some(indicator) = indicator.as_mut() {
+                indicator.update()?;
+            }
+
+            // 远程命令在两次刷新之间处理，最多延迟一个刷新周期
+            let mut read_now = false;
+            if let Some(publisher) = mqtt_publisher.as_mut() {
+                while let Some(command) = publisher.next_command() {
+                    log::info!("收到 MQTT 命令: {command:?}");
+                    publisher.ack(command);
+                    match command {
+                        Command::Read => read_now = true,
+                        Command::SetInterval(interval) => monitor.sample_interval = interval,
+                        Command::Reboot => {
+                            // 本周期的读数已写入数据库，等待 MQTT 任务发出回复后重启
+                            sleep(Duration::from_secs(1));
+                            power::restart();
+                        }
+                    }
+                }
+            }
+            if read_now {
+                break;
+            }
+
+            sleep(monitor.refresh_period());
         }
 
         // 数据读取
-        if let Some(latest_slot) = time_db.latest() {
+        let latest = time_db.lock().unwrap().latest();
+        if let Some(latest_slot) = latest {
             log::info!("最新数据: {latest_slot}");
         } else {
             log::info!("数据库中无数据");
         }
-        cnt -= 1;
-        if cnt == 0 {
-            break;
-        }
-    }
-
-    // screen.draw_example()?;
 
-    loop {
-        sleep(Duration::from_secs(1));
+        // 低功耗模式：读数已存储并显示，睡眠到下一次采样，唤醒后从头启动
+        if let Some(interval) = config::DEEP_SLEEP_INTERVAL {
+            power::deep_sleep_for(interval);
+        }
     }
-
-    // Ok(())
 }
@@ -1,4 +1,8 @@
 pub mod temperature_sensor;
+#[cfg(feature = "ds18b20")]
+pub mod ds18b20;
 pub mod flash;
 pub mod wifi;
-pub mod screen;
\ No newline at end of file
+pub mod screen;
+pub mod indicator;
+pub mod power;
\ No newline at end of file
@@ -0,0 +1,86 @@
+//! DS18B20 单总线温度探头
+//!
+//! 适用于防水探头等只测温度的场景。读数同样返回 `InfoSlot`，可以直接写入
+//! 时序数据库；DS18B20 不测湿度，湿度固定为 `NO_HUMIDITY`（0%）。
+//!
+//! 数据引脚需外接 4.7kΩ 上拉电阻。
+
+use crate::data::info_def::InfoSlot;
+use ds18b20::{Ds18b20, Resolution};
+use esp_idf_svc::hal::{
+    delay::Ets,
+    gpio::{AnyIOPin, InputOutput, PinDriver},
+};
+use esp_idf_svc::sys::EspError;
+use one_wire_bus::OneWire;
+use std::time::Duration;
+use thiserror::Error;
+
+/// DS18B20 没有湿度传感器，InfoSlot 中的湿度固定为该值
+pub const NO_HUMIDITY: f32 = 0.0;
+
+#[derive(Error, Debug)]
+pub enum Ds18b20Error {
+    #[error("Pin 配置失败: {0}")]
+    PinConfig(#[from] EspError),
+    #[error("单总线通信失败: {0}")]
+    OneWire(String),
+    #[error("总线上未找到 DS18B20")]
+    NotFound,
+}
+
+impl From<one_wire_bus::OneWireError<EspError>> for Ds18b20Error {
+    fn from(e: one_wire_bus::OneWireError<EspError>) -> Self {
+        Ds18b20Error::OneWire(format!("{e:?}"))
+    }
+}
+
+/// DS18B20 温度探头，使用总线上找到的第一个 DS18B20
+pub struct Ds18b20Sensor {
+    bus: OneWire<PinDriver<'static, AnyIOPin, InputOutput>>,
+    sensor: Ds18b20,
+    delay: Ets,
+}
+
+impl Ds18b20Sensor {
+    /// 从单个 GPIO pin 创建 DS18B20 实例，并在总线上搜索设备
+    ///
+    /// # Arguments
+    /// * `data_pin` - 单总线数据引脚
+    ///
+    /// # Returns
+    /// * `Result<Self, Ds18b20Error>` - 总线上没有 DS18B20 时返回 `NotFound`
+    pub fn from_pin(data_pin: impl Into<AnyIOPin>) -> Result<Self, Ds18b20Error> {
+        let pin = PinDriver::input_output_od(data_pin.into())?;
+        let mut bus = OneWire::new(pin)?;
+        let mut delay = Ets;
+
+        let mut search_state = None;
+        while let Some((address, state)) =
+            bus.device_search(search_state.as_ref(), false, &mut delay)?
+        {
+            search_state = Some(state);
+            if address.family_code() != ds18b20::FAMILY_CODE {
+                continue;
+            }
+            log::info!("找到 DS18B20: {address:?}");
+            let sensor = Ds18b20::new::<EspError>(address)?;
+            return Ok(Self { bus, sensor, delay });
+        }
+        Err(Ds18b20Error::NotFound)
+    }
+
+    /// 读取温度并返回 InfoSlot，湿度为 `NO_HUMIDITY`
+    ///
+    /// 每次读取都会启动一次转换，12 位分辨率下需等待约 750ms，期间线程休眠
+    pub fn read_data(&mut self) -> Result<InfoSlot, Ds18b20Error> {
+        self.sensor
+            .start_temp_measurement(&mut self.bus, &mut self.delay)?;
+        std::thread::sleep(Duration::from_millis(
+            Resolution::Bits12.max_measurement_time_millis().into(),
+        ));
+        let data = self.sensor.read_data(&mut self.bus, &mut self.delay)?;
+        log::debug!("DS18B20 读取成功: 温度 {:.2}°C", data.temperature);
+        Ok(InfoSlot::new_from_f32(data.temperature, NO_HUMIDITY))
+    }
+}
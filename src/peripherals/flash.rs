@@ -1,7 +1,9 @@
-use crate::utils::calculate::quick_align;
+use crate::utils::calculate::{align_down, crc16_ccitt, quick_align};
 use embedded_storage::nor_flash::{ErrorType, NorFlashError};
 use esp_idf_sys::esp;
 use std::ffi::CStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use thiserror;
 
 const FLASH_MAGIC: &CStr = c"CUSTOM_FLASH";
@@ -27,10 +29,16 @@ pub enum FlashError {
     NotAligned(usize, usize),
     #[error("Out of bounds access at address {0}, size {1}, flash size {2}")]
     OutOfBounds(usize, usize, usize),
+    #[error("Partition \"{0}\" not found")]
+    PartitionNotFound(String),
     #[error("Invalid flash header magic")]
     InvalidHeaderMagic,
+    #[error("Flash header CRC mismatch")]
+    HeaderCrcMismatch,
     #[error("Raw pointer cast failed in 0x{0:x}")]
     PointerCastFailed(usize),
+    #[error("Flash has been closed")]
+    Closed,
     #[error("Unknown error: {0}")]
     Unknown(i32),
 }
@@ -53,24 +61,70 @@ impl ErrorType for Flash {
     type Error = FlashError;
 }
 
+/// 分区头部的校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderStatus {
+    /// 魔数和 CRC 均正确
+    Valid,
+    /// 加入 CRC 之前的固件写入的头部：魔数正确，CRC 位置仍是擦除后的 0xFFFF
+    Legacy,
+    /// 魔数正确但 CRC 不匹配，头部已损坏
+    CrcMismatch,
+    /// 魔数不匹配，分区尚未初始化
+    BadMagic,
+}
+
+/// 分区头部，位于分区的第一个扇区
+///
+/// `crc` 覆盖魔数、`size` 和 `sector_size`，魔数完好但其余字段损坏的头部同样视为无效，
+/// 分区会被重新初始化。
+///
+/// 旧固件写入的头部没有 `crc` 字段，该位置在分区初始化时被擦除为 0xFF，
+/// 打开时识别为 `HeaderStatus::Legacy` 并原地补写 CRC，已有数据保持不变。
 #[repr(C, align(4))]
 pub struct FlashHEADER {
     magic: [u8; count_magic_bytes()],
     size: usize,
     sector_size: usize,
+    crc: u16,
 }
 
 impl FlashHEADER {
     pub fn new(size: usize, sector_size: usize) -> Self {
-        FlashHEADER {
+        let mut header = FlashHEADER {
             magic: magic_as_bytes(),
             size,
             sector_size,
+            crc: 0,
+        };
+        header.crc = header.compute_crc();
+        header
+    }
+
+    /// 校验魔数和 CRC
+    pub fn status(&self) -> HeaderStatus {
+        if self.magic != magic_as_bytes() {
+            HeaderStatus::BadMagic
+        } else if self.crc == self.compute_crc() {
+            HeaderStatus::Valid
+        } else if self.crc == LEGACY_HEADER_CRC {
+            HeaderStatus::Legacy
+        } else {
+            HeaderStatus::CrcMismatch
         }
     }
 
+    /// 头部是否可用，旧格式的头部同样可用
     pub fn is_valid(&self) -> bool {
-        self.magic == magic_as_bytes()
+        matches!(self.status(), HeaderStatus::Valid | HeaderStatus::Legacy)
+    }
+
+    fn compute_crc(&self) -> u16 {
+        let mut bytes = Vec::with_capacity(count_magic_bytes() + 2 * std::mem::size_of::<usize>());
+        bytes.extend_from_slice(&self.magic);
+        bytes.extend_from_slice(&self.size.to_le_bytes());
+        bytes.extend_from_slice(&self.sector_size.to_le_bytes());
+        crc16_ccitt(&bytes)
     }
 
     unsafe fn from_raw(ptr: *const u8) -> Self {
@@ -87,27 +141,73 @@ impl FlashHEADER {
 }
 
 const FLASH_HEADER_SIZE: usize = std::mem::size_of::<FlashHEADER>();
+
+/// 旧格式头部中 `crc` 位置的值（擦除后的 Flash）
+const LEGACY_HEADER_CRC: u16 = 0xFFFF;
 const FLASH_TYPE_CUSTOM: u32 = 0x40;
 
+/// 默认使用的自定义分区名
+pub const DEFAULT_PARTITION_LABEL: &CStr = c"tsdb";
+
+/// 按名称查找自定义类型的分区
+fn find_partition(label: &CStr) -> Result<*const esp_idf_sys::esp_partition_t, FlashError> {
+    let partition = unsafe {
+        esp_idf_sys::esp_partition_find_first(
+            FLASH_TYPE_CUSTOM,
+            esp_idf_sys::esp_partition_type_t_ESP_PARTITION_TYPE_ANY,
+            label.as_ptr(),
+        )
+    };
+
+    if partition.is_null() {
+        return Err(FlashError::PartitionNotFound(label.to_string_lossy().into_owned()));
+    }
+    Ok(partition)
+}
+
+/// Flash 物理写入/擦除字节数统计（自启动起累计，不含分区头部）
+///
+/// Flash 被 TSDB 持有后无法再直接访问，通过 `Flash::stats` 获取共享句柄读取统计
+#[derive(Debug, Default)]
+pub struct FlashStats {
+    bytes_written: AtomicUsize,
+    bytes_erased: AtomicUsize,
+}
+
+impl FlashStats {
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_erased(&self) -> usize {
+        self.bytes_erased.load(Ordering::Relaxed)
+    }
+
+    fn record_write(&self, len: usize) {
+        self.bytes_written.fetch_add(len, Ordering::Relaxed);
+    }
+
+    fn record_erase(&self, len: usize) {
+        self.bytes_erased.fetch_add(len, Ordering::Relaxed);
+    }
+}
+
 pub struct Flash {
     size: usize,
     sector_size: usize,
     partition: *const esp_idf_sys::esp_partition_t,
+    stats: Arc<FlashStats>,
 }
 
 impl Flash {
+    /// 读取默认分区 `DEFAULT_PARTITION_LABEL` 的头部
     pub fn touch_header() -> Result<FlashHEADER, FlashError> {
-        let partition = unsafe {
-            esp_idf_sys::esp_partition_find_first(
-                FLASH_TYPE_CUSTOM,
-                esp_idf_sys::esp_partition_type_t_ESP_PARTITION_TYPE_ANY,
-                c"tsdb".as_ptr(),
-            )
-        };
+        Self::touch_header_with_label(DEFAULT_PARTITION_LABEL)
+    }
 
-        if partition.is_null() {
-            return Err(FlashError::PointerCastFailed(0));
-        }
+    /// 读取名为 `label` 的自定义分区的头部
+    pub fn touch_header_with_label(label: &CStr) -> Result<FlashHEADER, FlashError> {
+        let partition = find_partition(label)?;
 
         let mut header_buf = [0u8; FLASH_HEADER_SIZE];
 
@@ -123,30 +223,26 @@ impl Flash {
 
         let header = unsafe { FlashHEADER::from_raw(header_buf.as_ptr()) };
 
-        if !header.is_valid() {
-            return Err(FlashError::InvalidHeaderMagic);
+        match header.status() {
+            HeaderStatus::Valid | HeaderStatus::Legacy => Ok(header),
+            HeaderStatus::CrcMismatch => Err(FlashError::HeaderCrcMismatch),
+            HeaderStatus::BadMagic => Err(FlashError::InvalidHeaderMagic),
         }
-
-        Ok(header)
     }
 
+    /// 打开默认分区 `DEFAULT_PARTITION_LABEL`
     pub fn new(size: usize, reset: bool) -> Result<Self, FlashError> {
+        Self::new_with_label(size, reset, DEFAULT_PARTITION_LABEL)
+    }
+
+    /// 打开名为 `label` 的自定义分区，头部无效或 `reset` 为 true 时重新初始化
+    pub fn new_with_label(size: usize, reset: bool, label: &CStr) -> Result<Self, FlashError> {
         if size == 0 {
             return Err(FlashError::Unknown(-1));
         }
         log::info!("HEADER SIZE: {FLASH_HEADER_SIZE}");
 
-        let partition = unsafe {
-            esp_idf_sys::esp_partition_find_first(
-                FLASH_TYPE_CUSTOM,
-                esp_idf_sys::esp_partition_type_t_ESP_PARTITION_TYPE_ANY,
-                c"tsdb".as_ptr(),
-            )
-        };
-
-        if partition.is_null() {
-            return Err(FlashError::PointerCastFailed(0));
-        }
+        let partition = find_partition(label)?;
 
         let sector_size = unsafe {
             match partition.as_ref() {
@@ -170,6 +266,12 @@ impl Flash {
         esp!(ret).map_err(|_| FlashError::Unknown(ret))?;
 
         let header = unsafe { FlashHEADER::from_raw(header_buf.as_ptr()) };
+        let status = header.status();
+
+        if status == HeaderStatus::Legacy && !reset {
+            log::warn!("Flash header has no CRC (written by an older firmware), adding it in place");
+            Self::write_header_crc(partition, &header)?;
+        }
 
         if !header.is_valid() || reset {
             let last = size + sector_size;
@@ -181,7 +283,7 @@ impl Flash {
             }
             if !header.is_valid() {
                 // 执行初始化操作
-                log::warn!("Flash header is invalid, resetting partition");
+                log::warn!("Flash header is invalid ({status:?}), resetting partition");
             }
             return Flash::reset(size, partition);
         }
@@ -190,7 +292,26 @@ impl Flash {
         log::info!("Flash partition found with size: {}", header.size);
         let size = header.size;
 
-        Ok(Flash { size, sector_size, partition })
+        Ok(Flash { size, sector_size, partition, stats: Arc::default() })
+    }
+
+    /// 为旧格式的头部补写 CRC
+    ///
+    /// 旧头部的 `crc` 位置为擦除状态，写入只会把位从 1 变为 0，无需先擦除扇区
+    fn write_header_crc(
+        partition: *const esp_idf_sys::esp_partition_t,
+        header: &FlashHEADER,
+    ) -> Result<(), FlashError> {
+        let crc = header.compute_crc();
+        let ret = unsafe {
+            esp_idf_sys::esp_partition_write(
+                partition,
+                std::mem::offset_of!(FlashHEADER, crc),
+                &crc as *const u16 as *const std::ffi::c_void,
+                std::mem::size_of::<u16>(),
+            )
+        };
+        esp!(ret).map_err(|_| FlashError::Unknown(ret))
     }
 
     pub fn reset(
@@ -236,19 +357,17 @@ impl Flash {
 
         esp!(ret).map_err(|_| FlashError::Unknown(ret))?;
 
-        Ok(Flash { size, sector_size, partition })
+        Ok(Flash { size, sector_size, partition, stats: Arc::default() })
     }
 
     pub fn flash_read(&self, offset: usize, buf: &mut [u8]) -> Result<(), FlashError> {
-        // 先检查是否越界
-        if self.sector_size + offset + buf.len() > self.size {
-            return Err(FlashError::OutOfBounds(offset, buf.len(), self.size));
-        }
-
         if self.partition.is_null() {
-            return Err(FlashError::PointerCastFailed(0));
+            return Err(FlashError::Closed);
         }
 
+        // 先检查是否越界
+        self.check_bounds(offset, buf.len())?;
+
         let ret = unsafe {
             esp_idf_sys::esp_partition_read(
                 self.partition,
@@ -264,15 +383,13 @@ impl Flash {
     }
 
     pub fn flash_write(&self, offset: usize, buf: &[u8]) -> Result<(), FlashError> {
-        // 先检查是否越界
-        if self.sector_size + offset + buf.len() > self.size {
-            return Err(FlashError::OutOfBounds(offset, buf.len(), self.size));
-        }
-
         if self.partition.is_null() {
-            return Err(FlashError::PointerCastFailed(0));
+            return Err(FlashError::Closed);
         }
 
+        // 先检查是否越界
+        self.check_bounds(offset, buf.len())?;
+
         let ret = unsafe {
             esp_idf_sys::esp_partition_write(
                 self.partition,
@@ -283,25 +400,57 @@ impl Flash {
         };
 
         esp!(ret).map_err(|_| FlashError::Unknown(ret))?;
+        self.stats.record_write(buf.len());
 
         Ok(())
     }
 
-    pub fn flash_erase(&self, offset: usize, len: usize) -> Result<(), FlashError> {
+    /// 写入任意位置的数据，必要时先擦除所在扇区
+    ///
+    /// NOR Flash 写入只能把位从 1 变为 0，直接覆盖已写过的数据会静默出错。
+    /// 该方法逐个读取写入范围涉及的扇区，合并新数据；只有需要把某些位从 0 变回 1 时
+    /// 才擦除整个扇区并写回，否则直接写入。写入范围可以跨越多个扇区。
+    pub fn flash_write_safe(&self, offset: usize, buf: &[u8]) -> Result<(), FlashError> {
+        if self.partition.is_null() {
+            return Err(FlashError::Closed);
+        }
+
         // 先检查是否越界
-        if self.sector_size + offset + len > self.size {
-            return Err(FlashError::OutOfBounds(offset, len, self.size));
+        self.check_bounds(offset, buf.len())?;
+
+        let mut sector = vec![0u8; self.sector_size];
+        for (sector_start, range) in sector_chunks(offset, buf.len(), self.sector_size) {
+            let data = &buf[range.start - offset..range.end - offset];
+            let within = range.start - sector_start..range.end - sector_start;
+
+            self.flash_read(sector_start, &mut sector)?;
+            if !needs_erase(&sector[within.clone()], data) {
+                self.flash_write(range.start, data)?;
+                continue;
+            }
+
+            sector[within].copy_from_slice(data);
+            self.flash_erase(sector_start, self.sector_size)?;
+            self.flash_write(sector_start, &sector)?;
         }
 
+        Ok(())
+    }
+
+    pub fn flash_erase(&self, offset: usize, len: usize) -> Result<(), FlashError> {
         if self.partition.is_null() {
-            return Err(FlashError::PointerCastFailed(0));
+            return Err(FlashError::Closed);
         }
 
+        // 先检查是否越界
+        self.check_bounds(offset, len)?;
+
         let ret = unsafe {
             esp_idf_sys::esp_partition_erase_range(self.partition, offset + self.sector_size, len)
         };
 
         esp!(ret).map_err(|_| FlashError::Unknown(ret))?;
+        self.stats.record_erase(len);
 
         Ok(())
     }
@@ -309,6 +458,38 @@ impl Flash {
     pub fn flash_capacity(&self) -> usize {
         self.size - self.sector_size
     }
+
+    /// 检查 `[offset, offset + len)` 是否位于可用区域（不含头部扇区）内
+    fn check_bounds(&self, offset: usize, len: usize) -> Result<(), FlashError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.flash_capacity() => Ok(()),
+            _ => Err(FlashError::OutOfBounds(offset, len, self.size)),
+        }
+    }
+
+    /// 关闭 Flash，释放分区指针
+    ///
+    /// 分区读写均为同步操作，没有需要额外刷新的缓存。关闭后分区指针被置空，
+    /// 之后不得再使用该指针，所有读写擦除操作都会返回 `FlashError::Closed`。
+    pub fn close(&mut self) -> Result<(), FlashError> {
+        if self.partition.is_null() {
+            return Err(FlashError::Closed);
+        }
+
+        self.partition = std::ptr::null();
+        log::info!("Flash partition closed");
+        Ok(())
+    }
+
+    /// 是否已经关闭
+    pub fn is_closed(&self) -> bool {
+        self.partition.is_null()
+    }
+
+    /// 获取写入/擦除统计的共享句柄，需在 Flash 交给 TSDB 之前调用
+    pub fn stats(&self) -> Arc<FlashStats> {
+        self.stats.clone()
+    }
 }
 
 impl embedded_storage::nor_flash::ReadNorFlash for Flash {
@@ -336,3 +517,135 @@ impl embedded_storage::nor_flash::NorFlash for Flash {
         self.flash_write(offset as usize, bytes)
     }
 }
+
+/// 将 `[offset, offset + len)` 按扇区拆分，返回每段所在扇区的起始地址和该段的地址范围
+fn sector_chunks(
+    offset: usize,
+    len: usize,
+    sector_size: usize,
+) -> impl Iterator<Item = (usize, std::ops::Range<usize>)> {
+    let end = offset + len;
+    // 长度为 0 时没有需要写入的扇区
+    let first_sector = if len == 0 { end } else { align_down(offset, sector_size) };
+    (first_sector..end)
+        .step_by(sector_size)
+        .map(move |sector_start| {
+            let start = sector_start.max(offset);
+            let stop = (sector_start + sector_size).min(end);
+            (sector_start, start..stop)
+        })
+}
+
+/// 写入 `new` 是否需要先擦除：`old` 中存在需要从 0 变为 1 的位
+fn needs_erase(old: &[u8], new: &[u8]) -> bool {
+    old.iter().zip(new).any(|(&o, &n)| o & n != n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_after_close() {
+        // 分区指针为空的 Flash 等价于已关闭，不会访问真实分区
+        let flash = Flash {
+            size: 4096 * 2,
+            sector_size: 4096,
+            partition: std::ptr::null(),
+            stats: Arc::default(),
+        };
+        assert!(flash.is_closed());
+
+        let mut buf = [0u8; 4];
+        assert!(matches!(flash.flash_read(0, &mut buf), Err(FlashError::Closed)));
+        assert!(matches!(flash.flash_write(0, &buf), Err(FlashError::Closed)));
+        assert!(matches!(flash.flash_write_safe(0, &buf), Err(FlashError::Closed)));
+        assert!(matches!(flash.flash_erase(0, 4096), Err(FlashError::Closed)));
+        // 失败的操作不计入统计
+        assert_eq!(flash.stats().bytes_written(), 0);
+        assert_eq!(flash.stats().bytes_erased(), 0);
+
+        let mut flash = flash;
+        assert!(matches!(flash.close(), Err(FlashError::Closed)));
+    }
+
+    #[test]
+    fn test_sector_chunks() {
+        let chunks: Vec<_> = sector_chunks(100, 50, 4096).collect();
+        assert_eq!(chunks, vec![(0, 100..150)]);
+
+        // 跨越三个扇区
+        let chunks: Vec<_> = sector_chunks(4000, 4096 + 200, 4096).collect();
+        assert_eq!(
+            chunks,
+            vec![(0, 4000..4096), (4096, 4096..8192), (8192, 8192..8296)]
+        );
+
+        // 恰好对齐整个扇区
+        let chunks: Vec<_> = sector_chunks(4096, 4096, 4096).collect();
+        assert_eq!(chunks, vec![(4096, 4096..8192)]);
+
+        assert_eq!(sector_chunks(10, 0, 4096).count(), 0);
+    }
+
+    #[test]
+    fn test_needs_erase() {
+        // 擦除后的 0xFF 可以写入任意值
+        assert!(!needs_erase(&[0xFF, 0xFF], &[0x12, 0x00]));
+        // 只清除位不需要擦除
+        assert!(!needs_erase(&[0b1010], &[0b1000]));
+        // 需要把 0 变为 1
+        assert!(needs_erase(&[0xFF, 0b0001], &[0xFF, 0b0011]));
+    }
+
+    #[test]
+    fn test_bounds_at_capacity() {
+        let flash = Flash {
+            size: 4096 * 2,
+            sector_size: 4096,
+            partition: std::ptr::null(),
+            stats: Arc::default(),
+        };
+        let capacity = flash.flash_capacity();
+        assert_eq!(capacity, 4096);
+
+        // 最后一个字节可以访问
+        assert!(flash.check_bounds(capacity - 1, 1).is_ok());
+        assert!(flash.check_bounds(0, capacity).is_ok());
+        assert!(flash.check_bounds(capacity, 0).is_ok());
+
+        // 超出一个字节
+        assert!(matches!(
+            flash.check_bounds(capacity, 1),
+            Err(FlashError::OutOfBounds(4096, 1, 8192))
+        ));
+        assert!(flash.check_bounds(capacity - 1, 2).is_err());
+        // 偏移加长度溢出时同样报告越界
+        assert!(flash.check_bounds(usize::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_header_crc() {
+        let header = FlashHEADER::new(4096 * 5, 4096);
+        assert!(header.is_valid());
+
+        // 魔数完好但 size 的一个字节损坏，视为无效，会触发重新初始化
+        let mut corrupted = FlashHEADER::new(4096 * 5, 4096);
+        corrupted.size ^= 0x0100;
+        assert!(!corrupted.is_valid());
+
+        assert_eq!(corrupted.status(), HeaderStatus::CrcMismatch);
+
+        let mut bad_magic = FlashHEADER::new(4096 * 5, 4096);
+        bad_magic.magic[0] ^= 0xFF;
+        assert!(!bad_magic.is_valid());
+        assert_eq!(bad_magic.status(), HeaderStatus::BadMagic);
+
+        // 旧固件写入的头部没有 CRC，该位置为擦除状态，仍然可用，数据不会被清除
+        let mut legacy = FlashHEADER::new(4096 * 5, 4096);
+        assert_ne!(legacy.crc, LEGACY_HEADER_CRC);
+        legacy.crc = LEGACY_HEADER_CRC;
+        assert_eq!(legacy.status(), HeaderStatus::Legacy);
+        assert!(legacy.is_valid());
+    }
+}
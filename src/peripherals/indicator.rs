@@ -0,0 +1,236 @@
+//! 报警指示输出
+//!
+//! 通过一个 GPIO 驱动 LED 或有源蜂鸣器，支持常亮、关闭、闪烁和交替模式。
+//!
+//! # 引脚要求
+//! - 引脚需支持推挽输出，并通过 `PinConfig::indicator` 在 GPIO 管理器中预留，
+//!   避免与传感器、屏幕引脚冲突
+//! - 有源蜂鸣器直接接在引脚上即可发声；LED 需串联限流电阻
+//! - 低电平点亮的模块请使用 `Indicator::new_active_low`
+
+use esp_idf_svc::hal::gpio::{AnyIOPin, Output, PinDriver};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum IndicatorError {
+    #[error("Pin 配置失败: {0}")]
+    PinConfig(#[from] esp_idf_svc::sys::EspError),
+}
+
+/// 闪烁模式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlinkPattern {
+    /// 每个周期中输出有效的时长
+    pub on: Duration,
+    /// 每个周期中输出无效的时长
+    pub off: Duration,
+    /// 重复次数，`None` 表示一直闪烁
+    pub repeat: Option<u32>,
+}
+
+impl BlinkPattern {
+    /// 慢闪：亮 500ms，灭 500ms
+    pub const SLOW: Self = Self::new(Duration::from_millis(500), Duration::from_millis(500));
+
+    /// 快闪：亮 100ms，灭 100ms
+    pub const FAST: Self = Self::new(Duration::from_millis(100), Duration::from_millis(100));
+
+    pub const fn new(on: Duration, off: Duration) -> Self {
+        Self {
+            on,
+            off,
+            repeat: None,
+        }
+    }
+
+    /// 设置重复次数，结束后输出保持无效
+    pub const fn times(mut self, repeat: u32) -> Self {
+        self.repeat = Some(repeat);
+        self
+    }
+
+    /// 计算从模式开始经过 `elapsed` 后输出是否有效
+    pub fn is_on_at(&self, elapsed: Duration) -> bool {
+        let period = self.on + self.off;
+        if period.is_zero() {
+            return false;
+        }
+
+        // 按纳秒计算，周期不足 1ms 时也不会除以 0
+        let period_ns = period.as_nanos();
+        let elapsed_ns = elapsed.as_nanos();
+        let cycle = elapsed_ns / period_ns;
+        if let Some(repeat) = self.repeat {
+            if cycle >= repeat as u128 {
+                return false;
+            }
+        }
+
+        elapsed_ns % period_ns < self.on.as_nanos()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Off,
+    On,
+    Blink { pattern: BlinkPattern, started: Instant },
+    Alternate { active: bool },
+}
+
+impl Mode {
+    /// 推进一次状态并返回本次的输出是否有效
+    fn step(&mut self) -> bool {
+        match self {
+            Mode::Off => false,
+            Mode::On => true,
+            Mode::Blink { pattern, started } => pattern.is_on_at(started.elapsed()),
+            Mode::Alternate { active } => {
+                *active = !*active;
+                *active
+            }
+        }
+    }
+}
+
+/// 报警指示器，驱动 LED 或有源蜂鸣器
+pub struct Indicator {
+    pin: PinDriver<'static, AnyIOPin, Output>,
+    active_low: bool,
+    mode: Mode,
+}
+
+impl Indicator {
+    /// 从 GPIO 引脚创建指示器，高电平有效
+    ///
+    /// # Arguments
+    /// * `pin` - 指示器引脚，通常来自 `GPIOConfig::indicator_pin`
+    pub fn new(pin: impl Into<AnyIOPin>) -> Result<Self, IndicatorError> {
+        Self::with_polarity(pin.into(), false)
+    }
+
+    /// 从 GPIO 引脚创建指示器，低电平有效
+    pub fn new_active_low(pin: impl Into<AnyIOPin>) -> Result<Self, IndicatorError> {
+        Self::with_polarity(pin.into(), true)
+    }
+
+    fn with_polarity(pin: AnyIOPin, active_low: bool) -> Result<Self, IndicatorError> {
+        let pin = PinDriver::output(pin)?;
+        let mut indicator = Self {
+            pin,
+            active_low,
+            mode: Mode::Off,
+        };
+        indicator.write(false)?;
+        Ok(indicator)
+    }
+
+    /// 输出常有效
+    pub fn on(&mut self) -> Result<(), IndicatorError> {
+        self.mode = Mode::On;
+        self.write(true)
+    }
+
+    /// 关闭输出
+    pub fn off(&mut self) -> Result<(), IndicatorError> {
+        self.mode = Mode::Off;
+        self.write(false)
+    }
+
+    /// 按指定模式闪烁，需要周期性调用 `update` 推进状态
+    pub fn blink(&mut self, pattern: BlinkPattern) -> Result<(), IndicatorError> {
+        self.mode = Mode::Blink {
+            pattern,
+            started: Instant::now(),
+        };
+        self.update()
+    }
+
+    /// 每次调用 `update` 切换一次输出，闪烁节奏与调用方的刷新节奏一致
+    ///
+    /// `update` 的调用间隔与闪烁周期相同时，`blink` 按经过时间计算的相位可能连续落在
+    /// 同一半周期内而不切换，按固定节奏刷新时应使用该模式
+    pub fn alternate(&mut self) -> Result<(), IndicatorError> {
+        self.mode = Mode::Alternate { active: true };
+        self.write(true)
+    }
+
+    /// 是否处于非关闭状态（常亮、闪烁或交替）
+    pub fn is_active(&self) -> bool {
+        !matches!(self.mode, Mode::Off)
+    }
+
+    /// 根据当前模式刷新引脚电平，闪烁和交替模式下应在主循环中周期性调用
+    pub fn update(&mut self) -> Result<(), IndicatorError> {
+        let active = self.mode.step();
+        self.write(active)
+    }
+
+    fn write(&mut self, active: bool) -> Result<(), IndicatorError> {
+        if active != self.active_low {
+            self.pin.set_high()?;
+        } else {
+            self.pin.set_low()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blink_phases() {
+        let pattern = BlinkPattern::new(Duration::from_millis(100), Duration::from_millis(300));
+
+        assert!(pattern.is_on_at(Duration::ZERO));
+        assert!(pattern.is_on_at(Duration::from_millis(99)));
+        assert!(!pattern.is_on_at(Duration::from_millis(100)));
+        assert!(!pattern.is_on_at(Duration::from_millis(399)));
+
+        // 第二个周期
+        assert!(pattern.is_on_at(Duration::from_millis(400)));
+        assert!(!pattern.is_on_at(Duration::from_millis(550)));
+    }
+
+    #[test]
+    fn test_blink_repeat_limit() {
+        let pattern = BlinkPattern::FAST.times(2);
+
+        assert!(pattern.is_on_at(Duration::from_millis(50)));
+        assert!(pattern.is_on_at(Duration::from_millis(250)));
+
+        // 两个周期后保持关闭
+        assert!(!pattern.is_on_at(Duration::from_millis(400)));
+        assert!(!pattern.is_on_at(Duration::from_millis(450)));
+    }
+
+    #[test]
+    fn test_blink_zero_period() {
+        let pattern = BlinkPattern::new(Duration::ZERO, Duration::ZERO);
+        assert!(!pattern.is_on_at(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_blink_sub_millisecond_period() {
+        let pattern = BlinkPattern::new(Duration::from_micros(500), Duration::from_micros(400));
+        assert!(pattern.is_on_at(Duration::from_micros(499)));
+        assert!(!pattern.is_on_at(Duration::from_micros(500)));
+        assert!(pattern.is_on_at(Duration::from_micros(900)));
+        assert!(!pattern.is_on_at(Duration::from_micros(10_700)));
+    }
+
+    #[test]
+    fn test_alternate_toggles_each_step() {
+        // `alternate` 开始时输出有效，之后每次 `update` 切换一次
+        let mut mode = Mode::Alternate { active: true };
+        assert!(!mode.step());
+        assert!(mode.step());
+        assert!(!mode.step());
+
+        assert!(!Mode::Off.step());
+        assert!(Mode::On.step());
+    }
+}
@@ -0,0 +1,101 @@
+//! 深度睡眠与唤醒
+//!
+//! 每次采样后调用 `deep_sleep_for` 进入深度睡眠，到时由 RTC 定时器唤醒，芯片从头重新启动。
+//!
+//! ## 深度睡眠后丢失的状态
+//! - 普通 RAM 中的全部变量：WiFi 连接、NTP 句柄、MQTT 离线队列、报警状态，
+//!   以及 `TimeDB` 的写入速率和数据校验进度等内存统计
+//! - GPIO 输出电平和外设配置，屏幕需要重新初始化
+//!
+//! ## 保持的状态
+//! - RTC 慢速内存（`.rtc.data` 段）：显示管道保存在 `rtc_window` 中的平滑窗口和本模块的时间有效标志
+//! - 系统时间：ESP-IDF 在睡眠期间用 RTC 定时器计时，唤醒后 `get_unix_timestamp`
+//!   直接读取 RTC 即可得到正确的时间，无需等待 NTP 重新同步
+//! - Flash 中的数据库记录
+
+use esp_idf_svc::hal::reset::ResetReason;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// DHT22 上电后需要等待的时间，数据手册要求至少 1 秒，这里留出余量
+pub const DHT22_WARMUP: Duration = Duration::from_secs(2);
+
+/// 系统时间是否已经同步过，保存在 RTC 内存中跨深度睡眠保持，冷启动时为 `false`
+#[link_section = ".rtc.data"]
+static RTC_TIME_VALID: AtomicBool = AtomicBool::new(false);
+
+/// 进入深度睡眠，`duration` 后由 RTC 定时器唤醒并重新启动，不会返回
+pub fn deep_sleep_for(duration: Duration) -> ! {
+    log::info!("进入深度睡眠 {}s", duration.as_secs());
+    let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+    unsafe { esp_idf_sys::esp_deep_sleep(micros) }
+}
+
+/// 软件重启，不会返回
+pub fn restart() -> ! {
+    log::info!("重启设备");
+    esp_idf_svc::hal::reset::restart()
+}
+
+/// 本次启动是否由深度睡眠唤醒
+pub fn woke_from_deep_sleep() -> bool {
+    ResetReason::get() == ResetReason::DeepSleep
+}
+
+/// 记录系统时间已同步，之后从深度睡眠唤醒时 `rtc_time_valid` 返回 `true`
+pub fn mark_rtc_time_valid() {
+    RTC_TIME_VALID.store(true, Ordering::Relaxed);
+}
+
+/// 从深度睡眠唤醒且睡眠前系统时间已同步，此时 RTC 维护的时间可以直接用于时间戳
+pub fn rtc_time_valid() -> bool {
+    woke_from_deep_sleep() && RTC_TIME_VALID.load(Ordering::Relaxed)
+}
+
+/// 传感器上电预热计时
+///
+/// 启动时创建，第一次读取传感器前调用 `wait_ready`，不足预热时间时阻塞等待剩余时间。
+/// 唤醒后的连接 WiFi 等初始化步骤耗时计入预热时间。
+pub struct SensorWarmup {
+    started: Instant,
+    warmup: Duration,
+}
+
+impl SensorWarmup {
+    pub fn start(warmup: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            warmup,
+        }
+    }
+
+    /// 等待预热完成，已完成时立即返回
+    pub fn wait_ready(&self) {
+        let remaining = remaining_warmup(self.started.elapsed(), self.warmup);
+        if !remaining.is_zero() {
+            log::info!("等待传感器预热 {}ms", remaining.as_millis());
+            sleep(remaining);
+        }
+    }
+}
+
+fn remaining_warmup(elapsed: Duration, warmup: Duration) -> Duration {
+    warmup.saturating_sub(elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining_warmup() {
+        let warmup = Duration::from_secs(2);
+        assert_eq!(remaining_warmup(Duration::ZERO, warmup), warmup);
+        assert_eq!(
+            remaining_warmup(Duration::from_millis(1500), warmup),
+            Duration::from_millis(500)
+        );
+        assert_eq!(remaining_warmup(Duration::from_secs(5), warmup), Duration::ZERO);
+    }
+}
@@ -1,17 +1,326 @@
 use anyhow::Result;
+use core::fmt;
+use display_interface::{DataFormat, DisplayError};
 use embedded_hal::spi::SpiDevice;
+use esp_idf_svc::hal::delay::{self, TickType};
 use esp_idf_svc::hal::gpio::{self, AnyIOPin, InputOutput, PinDriver};
-use esp_idf_svc::hal::spi::{SPI2, SpiConfig, SpiDeviceDriver, SpiDriver, SpiDriverConfig};
-use ssd1306::{prelude::*, Ssd1306};
+use esp_idf_svc::hal::i2c::{I2C0, I2cConfig, I2cDriver};
+use esp_idf_svc::hal::spi::{Dma, SPI2, SpiConfig, SpiDeviceDriver, SpiDriver, SpiDriverConfig};
+use esp_idf_svc::sys::{
+    esp, spi_device_get_trans_result, spi_device_queue_trans, spi_transaction_t,
+    spi_transaction_t__bindgen_ty_1, EspError, ESP_ERR_TIMEOUT,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use esp_idf_svc::hal::units::FromValueType;
+use ssd1306::{prelude::*, I2CDisplayInterface, Ssd1306};
 use ssd1306::mode::DisplayConfig;
 use embedded_graphics::{
-    mono_font::{iso_8859_1::FONT_6X10, iso_8859_1::FONT_9X18_BOLD, MonoTextStyle},
+    mono_font::{
+        iso_8859_1::{FONT_6X10, FONT_7X13, FONT_9X18_BOLD},
+        MonoFont, MonoTextStyle,
+    },
     pixelcolor::BinaryColor,
     prelude::*,
+    primitives::{Polyline, PrimitiveStyle, Rectangle},
     text::Text,
 };
 
 type IOPinDriver = PinDriver<'static, gpio::AnyIOPin, InputOutput>;
+type SpiBus = SPIInterface<SpiDeviceDriver<'static, SpiDriver<'static>>, IOPinDriver>;
+type I2cBus = I2CInterface<I2cDriver<'static>>;
+
+/// 设置对比度时使用的预充电周期，与 ssd1306 预设亮度一致
+const SCREEN_PRECHARGE: u8 = 0x2;
+
+/// SSD1306 常见的 I2C 地址，按探测顺序排列
+pub const SSD1306_I2C_ADDRESSES: [u8; 2] = [0x3C, 0x3D];
+
+/// 屏幕实际使用的接口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenInterface {
+    I2c { address: u8 },
+    Spi,
+}
+
+impl fmt::Display for ScreenInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenInterface::I2c { address } => write!(f, "I2C (0x{address:02X})"),
+            ScreenInterface::Spi => write!(f, "SPI"),
+        }
+    }
+}
+
+/// 自动检测得到的屏幕总线，I2C 和 SPI 共用同一个 `Screen` 类型
+pub enum DisplayBus {
+    I2c(I2cBus),
+    Spi(SpiBus),
+}
+
+impl WriteOnlyDataCommand for DisplayBus {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match self {
+            DisplayBus::I2c(bus) => bus.send_commands(cmd),
+            DisplayBus::Spi(bus) => bus.send_commands(cmd),
+        }
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match self {
+            DisplayBus::I2c(bus) => bus.send_data(buf),
+            DisplayBus::Spi(bus) => bus.send_data(buf),
+        }
+    }
+}
+
+/// 单色屏一帧的字节数，也是 DMA 单次传输的最大长度
+fn frame_bytes<SIZE: DisplaySize>() -> usize {
+    SIZE::WIDTH as usize * SIZE::HEIGHT as usize / 8
+}
+
+/// DMA 刷新的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlushState {
+    /// DMA 缓冲区空闲
+    Idle,
+    /// 帧数据已复制到 DMA 缓冲区，尚未开始传输
+    Staged,
+    /// DMA 传输进行中，缓冲区不能修改
+    InFlight,
+}
+
+/// DMA 刷新状态机，只记录状态，不访问硬件
+#[derive(Debug)]
+struct DmaFlushTracker {
+    state: FlushState,
+}
+
+impl DmaFlushTracker {
+    fn new() -> Self {
+        Self {
+            state: FlushState::Idle,
+        }
+    }
+
+    fn in_flight(&self) -> bool {
+        self.state == FlushState::InFlight
+    }
+
+    fn is_staged(&self) -> bool {
+        self.state == FlushState::Staged
+    }
+
+    /// 追加帧数据，返回是否需要先清空缓冲区（新一帧的第一块数据）
+    ///
+    /// 调用前必须等待进行中的传输完成
+    fn stage(&mut self) -> bool {
+        debug_assert!(!self.in_flight(), "DMA 传输期间不能修改缓冲区");
+        let fresh = self.state == FlushState::Idle;
+        self.state = FlushState::Staged;
+        fresh
+    }
+
+    /// 开始传输，返回是否有数据需要发送
+    fn start(&mut self) -> bool {
+        if self.state != FlushState::Staged {
+            return false;
+        }
+        self.state = FlushState::InFlight;
+        true
+    }
+
+    /// 传输完成或已阻塞发送，缓冲区恢复空闲
+    fn complete(&mut self) {
+        self.state = FlushState::Idle;
+    }
+}
+
+/// DMA 刷新使用的 SPI 设备和缓冲区
+///
+/// # 缓冲区所有权
+/// DMA 期间外设直接读取 `frame`，因此：
+/// - `frame` 和 `transaction` 在传输完成前不能被修改、重新分配或释放；
+///   状态为 `InFlight` 时任何写入都会先等待传输完成，drop 时也会等待
+/// - 帧数据是从 ssd1306 的帧缓冲区复制而来，传输期间可以继续在屏幕上绘制下一帧
+/// - `frame` 使用内部 RAM 分配（ESP32-C3 的内部 RAM 均可用于 DMA）
+struct DmaEngine {
+    spi: SpiDeviceDriver<'static, SpiDriver<'static>>,
+    dc: IOPinDriver,
+    frame: Vec<u8>,
+    /// 一帧的字节数，由屏幕尺寸决定
+    frame_bytes: usize,
+    // 地址在传输期间必须保持不变，因此放在堆上
+    transaction: Box<spi_transaction_t>,
+    tracker: DmaFlushTracker,
+}
+
+impl DmaEngine {
+    /// 阻塞等待进行中的传输完成
+    fn wait(&mut self) -> Result<(), EspError> {
+        if self.tracker.in_flight() {
+            let mut done: *mut spi_transaction_t = core::ptr::null_mut();
+            let result = esp!(unsafe {
+                spi_device_get_trans_result(self.spi.device(), &mut done, delay::BLOCK)
+            });
+            self.tracker.complete();
+            result?;
+        }
+        Ok(())
+    }
+
+    /// 非阻塞检查传输是否完成
+    fn poll(&mut self) -> bool {
+        if !self.tracker.in_flight() {
+            return true;
+        }
+        let mut done: *mut spi_transaction_t = core::ptr::null_mut();
+        match esp!(unsafe {
+            spi_device_get_trans_result(self.spi.device(), &mut done, delay::NON_BLOCK)
+        }) {
+            Err(e) if e.code() == ESP_ERR_TIMEOUT => false,
+            result => {
+                if let Err(e) = result {
+                    log::warn!("屏幕 DMA 传输失败: {e}");
+                }
+                self.tracker.complete();
+                true
+            }
+        }
+    }
+
+    /// 将缓冲区中的帧数据排入 DMA 队列后立即返回
+    fn start(&mut self) -> Result<(), EspError> {
+        if !self.tracker.start() {
+            return Ok(());
+        }
+        self.dc.set_high()?;
+        *self.transaction = spi_transaction_t {
+            __bindgen_anon_1: spi_transaction_t__bindgen_ty_1 {
+                tx_buffer: self.frame.as_ptr() as *const _,
+            },
+            length: (self.frame.len() * 8) as _,
+            ..Default::default()
+        };
+        let result = esp!(unsafe {
+            spi_device_queue_trans(self.spi.device(), &mut *self.transaction, delay::BLOCK)
+        });
+        if result.is_err() {
+            self.tracker.complete();
+        }
+        result
+    }
+
+    /// 阻塞发送命令；未开始传输的帧数据先以阻塞方式发出，保证顺序
+    fn send_command(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.wait().map_err(|_| DisplayError::BusWriteError)?;
+        if self.tracker.is_staged() {
+            self.dc.set_high().map_err(|_| DisplayError::DCError)?;
+            let result = self.spi.write(&self.frame);
+            self.tracker.complete();
+            result.map_err(|_| DisplayError::BusWriteError)?;
+        }
+        self.dc.set_low().map_err(|_| DisplayError::DCError)?;
+        self.spi.write(bytes).map_err(|_| DisplayError::BusWriteError)
+    }
+
+    /// 将帧数据复制到 DMA 缓冲区，等待 `start` 发送
+    fn stage_data(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.wait().map_err(|_| DisplayError::BusWriteError)?;
+        if self.tracker.stage() {
+            self.frame.clear();
+        }
+        if self.frame.len() + bytes.len() > self.frame_bytes {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        self.frame.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl Drop for DmaEngine {
+    fn drop(&mut self) {
+        // DMA 仍在读取缓冲区时不能释放
+        if let Err(e) = self.wait() {
+            log::warn!("等待屏幕 DMA 传输结束失败: {e}");
+        }
+    }
+}
+
+/// 使用 DMA 发送帧数据的 SPI 总线，命令仍以阻塞方式发送
+///
+/// ssd1306 刷新时先发送设置绘制区域的命令，再按页发送数据；
+/// 数据会先复制到 DMA 缓冲区，由 `Screen::flush_start` 一次性排入 DMA 队列。
+/// 水平寻址模式下连续的页数据会自动换行，合并发送与逐页发送等价。
+pub struct DmaSpiBus(Rc<RefCell<DmaEngine>>);
+
+impl WriteOnlyDataCommand for DmaSpiBus {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match cmd {
+            DataFormat::U8(bytes) => self.0.borrow_mut().send_command(bytes),
+            _ => Err(DisplayError::DataFormatNotImplemented),
+        }
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(bytes) => self.0.borrow_mut().stage_data(bytes),
+            _ => Err(DisplayError::DataFormatNotImplemented),
+        }
+    }
+}
+
+/// 屏幕文字字号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontSize {
+    /// 6x10，每行可显示 21 个字符
+    Small,
+    /// 7x13，每行可显示 18 个字符
+    Medium,
+    /// 9x18 粗体，每行可显示 14 个字符
+    Large,
+}
+
+impl FontSize {
+    /// 字号对应的等宽字体
+    pub fn font(self) -> &'static MonoFont<'static> {
+        match self {
+            FontSize::Small => &FONT_6X10,
+            FontSize::Medium => &FONT_7X13,
+            FontSize::Large => &FONT_9X18_BOLD,
+        }
+    }
+}
+
+/// 计算文本以指定字号绘制时的宽度（像素），多行文本取最长一行
+pub fn text_width(text: &str, size: FontSize) -> u32 {
+    let font = size.font();
+    let advance = font.character_size.width + font.character_spacing;
+    text.split('\n')
+        .map(|line| (line.chars().count() as u32 * advance).saturating_sub(font.character_spacing))
+        .max()
+        .unwrap_or(0)
+}
+
+/// 文本的水平对齐方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HorizontalAlign {
+    /// 居中，文本比屏幕宽时从左边缘开始
+    Center,
+    /// 右对齐，右边缘距屏幕右侧 `margin` 像素
+    Right { margin: i32 },
+}
+
+impl HorizontalAlign {
+    /// 单行文本在宽度为 `display_width` 的屏幕上的起始 x 坐标
+    fn line_x(self, line: &str, size: FontSize, display_width: u32) -> i32 {
+        let free = display_width as i32 - text_width(line, size) as i32;
+        match self {
+            HorizontalAlign::Center => (free / 2).max(0),
+            HorizontalAlign::Right { margin } => free - margin,
+        }
+    }
+}
 
 pub fn to_point(x: i32, y: i32) -> Point {
     Point::new(x, y)
@@ -46,7 +355,172 @@ impl ScreenBuilder {
         mosi: impl Into<AnyIOPin>,
         cs: impl Into<AnyIOPin>,
         dc: impl Into<AnyIOPin>,
-    ) -> Result<Screen<SpiDeviceDriver<'static, SpiDriver<'static>>>> {
+    ) -> Result<Screen<SpiBus>> {
+        Screen::from_interface(Self::spi_bus(spi2, sck, mosi, cs, dc)?)
+    }
+
+    /// 同 `with_pins`，但可以指定屏幕尺寸和旋转方向
+    ///
+    /// `with_pins` 使用 128x64、`Rotate0`；引脚的含义和默认推荐引脚与 `with_pins` 相同。
+    /// 128x32 面板使用 `DisplaySize128x32`，倒装的屏幕使用 `DisplayRotation::Rotate180`。
+    ///
+    /// # 示例
+    /// ```ignore
+    /// let screen = ScreenBuilder::with_pins_config(
+    ///     peripherals.spi2, sck, mosi, cs, dc,
+    ///     DisplaySize128x32,
+    ///     DisplayRotation::Rotate180,
+    /// )?;
+    /// ```
+    pub fn with_pins_config<SIZE: DisplaySize>(
+        spi2: SPI2,
+        sck: impl Into<AnyIOPin>,
+        mosi: impl Into<AnyIOPin>,
+        cs: impl Into<AnyIOPin>,
+        dc: impl Into<AnyIOPin>,
+        size: SIZE,
+        rotation: DisplayRotation,
+    ) -> Result<Screen<SpiBus, SIZE>> {
+        Screen::from_interface_with(Self::spi_bus(spi2, sck, mosi, cs, dc)?, size, rotation)
+    }
+
+    /// 从 I2C 外设和 GPIO pins 创建 I2C 接口（4 针模块）的 Screen 实例
+    ///
+    /// 绘制方法与 SPI 屏幕完全相同。大多数 0.96" 模块的地址为 0x3C，
+    /// 背面地址电阻焊到另一侧时为 0x3D，不确定时可以使用 `auto_detect` 探测。
+    /// 可复用默认 SPI 引脚的 GPIO2（SCL）和 GPIO0（SDA），I2C 频率为 400kHz。
+    ///
+    /// # Arguments
+    /// * `i2c0` - I2C0 外设
+    /// * `sda` - I2C SDA 引脚
+    /// * `scl` - I2C SCL 引脚
+    /// * `address` - 屏幕的 7 位 I2C 地址，通常为 0x3C
+    ///
+    /// # 示例
+    /// ```ignore
+    /// let screen = ScreenBuilder::with_i2c(peripherals.i2c0, sda, scl, 0x3C)?;
+    /// ```
+    pub fn with_i2c(
+        i2c0: I2C0,
+        sda: impl Into<AnyIOPin>,
+        scl: impl Into<AnyIOPin>,
+        address: u8,
+    ) -> Result<Screen<I2cBus>> {
+        let i2c = I2cDriver::new(i2c0, sda.into(), scl.into(), &Self::i2c_config())?;
+        Screen::from_interface(I2CDisplayInterface::new_custom_address(i2c, address))
+    }
+
+    /// 启动时自动检测屏幕接口，先探测 I2C，失败后回退到 SPI
+    ///
+    /// 常见的双接口模块中 D0/D1 同时作为 SCK/SCL 与 MOSI/SDA，
+    /// 因此 I2C 探测直接复用 SPI 的 SCK（SCL）和 MOSI（SDA）引脚，无需额外接线。
+    /// 依次探测 `SSD1306_I2C_ADDRESSES` 中的地址，任一地址应答即使用 I2C。
+    ///
+    /// # Arguments
+    /// * `i2c0` - I2C0 外设，仅探测和 I2C 模式下使用
+    /// * `spi2` - SPI2 外设，仅 SPI 模式下使用
+    /// * `sck` - SPI SCK / I2C SCL 引脚
+    /// * `mosi` - SPI MOSI / I2C SDA 引脚
+    /// * `cs` - SPI CS 片选引脚
+    /// * `dc` - 屏幕 DC (数据/命令) 引脚
+    ///
+    /// # Returns
+    /// * `Result<(Screen<DisplayBus>, ScreenInterface)>` - 屏幕实例和实际使用的接口
+    pub fn auto_detect(
+        mut i2c0: I2C0,
+        spi2: SPI2,
+        sck: impl Into<AnyIOPin>,
+        mosi: impl Into<AnyIOPin>,
+        cs: impl Into<AnyIOPin>,
+        dc: impl Into<AnyIOPin>,
+    ) -> Result<(Screen<DisplayBus>, ScreenInterface)> {
+        let mut sck: AnyIOPin = sck.into();
+        let mut mosi: AnyIOPin = mosi.into();
+
+        let interface = match I2cDriver::new(&mut i2c0, &mut mosi, &mut sck, &Self::i2c_config()) {
+            Ok(mut i2c) => {
+                let timeout = TickType::new_millis(50).ticks();
+                detect_interface(|address| i2c.write(address, &[], timeout).is_ok())
+            }
+            Err(e) => {
+                log::warn!("I2C 探测初始化失败: {e}，回退到 SPI");
+                ScreenInterface::Spi
+            }
+        };
+        log::info!("检测到屏幕接口: {interface}");
+
+        let bus = match interface {
+            ScreenInterface::I2c { address } => {
+                let i2c = I2cDriver::new(i2c0, mosi, sck, &Self::i2c_config())?;
+                DisplayBus::I2c(I2CDisplayInterface::new_custom_address(i2c, address))
+            }
+            ScreenInterface::Spi => DisplayBus::Spi(Self::spi_bus(spi2, sck, mosi, cs, dc)?),
+        };
+
+        Ok((Screen::from_interface(bus)?, interface))
+    }
+
+    /// 同 `with_pins`，但帧数据通过 DMA 发送，`Screen::flush_start` 不会阻塞等待传输完成
+    pub fn with_pins_dma(
+        spi2: SPI2,
+        sck: impl Into<AnyIOPin>,
+        mosi: impl Into<AnyIOPin>,
+        cs: impl Into<AnyIOPin>,
+        dc: impl Into<AnyIOPin>,
+    ) -> Result<Screen<DmaSpiBus>> {
+        Self::with_pins_dma_config(
+            spi2,
+            sck,
+            mosi,
+            cs,
+            dc,
+            DisplaySize128x64,
+            DisplayRotation::Rotate0,
+        )
+    }
+
+    /// 同 `with_pins_config`，但帧数据通过 DMA 发送，DMA 缓冲区按屏幕尺寸分配
+    pub fn with_pins_dma_config<SIZE: DisplaySize>(
+        spi2: SPI2,
+        sck: impl Into<AnyIOPin>,
+        mosi: impl Into<AnyIOPin>,
+        cs: impl Into<AnyIOPin>,
+        dc: impl Into<AnyIOPin>,
+        size: SIZE,
+        rotation: DisplayRotation,
+    ) -> Result<Screen<DmaSpiBus, SIZE>> {
+        let frame_bytes = frame_bytes::<SIZE>();
+        let driver_config = SpiDriverConfig::new().dma(Dma::Auto(frame_bytes));
+        let spi = SpiDriver::new(
+            spi2,
+            sck.into(),
+            mosi.into(),
+            Option::<AnyIOPin>::None,
+            &driver_config,
+        )?;
+        let spi = SpiDeviceDriver::new(spi, Some(cs.into()), &SpiConfig::new().write_only(true))?;
+        let dc = PinDriver::input_output(dc.into())?;
+
+        let engine = Rc::new(RefCell::new(DmaEngine {
+            spi,
+            dc,
+            frame: Vec::with_capacity(frame_bytes),
+            frame_bytes,
+            transaction: Box::default(),
+            tracker: DmaFlushTracker::new(),
+        }));
+        let mut screen = Screen::from_interface_with(DmaSpiBus(engine.clone()), size, rotation)?;
+        screen.dma = Some(engine);
+        Ok(screen)
+    }
+
+    fn spi_bus(
+        spi2: SPI2,
+        sck: impl Into<AnyIOPin>,
+        mosi: impl Into<AnyIOPin>,
+        cs: impl Into<AnyIOPin>,
+        dc: impl Into<AnyIOPin>,
+    ) -> Result<SpiBus> {
         // 转换为 AnyIOPin
         let sck: AnyIOPin = sck.into();
         let mosi: AnyIOPin = mosi.into();
@@ -68,30 +542,59 @@ impl ScreenBuilder {
 
         // 创建 SPI 设备驱动
         let spi_device = SpiDeviceDriver::new(spi, Some(cs), &config)?;
+        let dc = PinDriver::input_output(dc)?;
+
+        Ok(SPIInterface::new(spi_device, dc))
+    }
 
-        // 创建屏幕
-        Screen::new(spi_device, dc)
+    fn i2c_config() -> I2cConfig {
+        I2cConfig::new().baudrate(400.kHz().into())
     }
+}
 
+/// 按 `SSD1306_I2C_ADDRESSES` 顺序探测 I2C 地址，第一个应答的地址胜出，全部无应答时使用 SPI
+fn detect_interface(mut probe: impl FnMut(u8) -> bool) -> ScreenInterface {
+    SSD1306_I2C_ADDRESSES
+        .into_iter()
+        .find(|&address| probe(address))
+        .map_or(ScreenInterface::Spi, |address| ScreenInterface::I2c { address })
 }
 
-pub struct Screen<SPI: SpiDevice> {
-    driver: Ssd1306<SPIInterface<SPI, IOPinDriver>, DisplaySize128x64, ssd1306::mode::BufferedGraphicsMode<DisplaySize128x64>>,
+/// SSD1306 屏幕，默认为 128x64 面板
+pub struct Screen<DI: WriteOnlyDataCommand, SIZE: DisplaySize = DisplaySize128x64> {
+    driver: Ssd1306<DI, SIZE, ssd1306::mode::BufferedGraphicsMode<SIZE>>,
+    /// 使用 DMA 总线时与 `DmaSpiBus` 共享的传输状态
+    dma: Option<Rc<RefCell<DmaEngine>>>,
+    /// 屏幕是否已通过 `sleep` 关闭显示
+    asleep: bool,
 }
 
-impl<SPI: SpiDevice> Screen<SPI> {
+impl<SPI: SpiDevice> Screen<SPIInterface<SPI, IOPinDriver>> {
     pub fn new(spi: SPI, dc_io: gpio::AnyIOPin) -> Result<Self> {
         let dc_io = PinDriver::input_output(dc_io)?;
 
-        let interface = SPIInterface::new(spi, dc_io);
-        let size = DisplaySize128x64;
-        let rotation = DisplayRotation::Rotate0;
+        Self::from_interface(SPIInterface::new(spi, dc_io))
+    }
+}
+
+impl<DI: WriteOnlyDataCommand> Screen<DI> {
+    /// 从任意显示接口创建并初始化 128x64、不旋转的屏幕
+    pub fn from_interface(interface: DI) -> Result<Self> {
+        Self::from_interface_with(interface, DisplaySize128x64, DisplayRotation::Rotate0)
+    }
+}
+
+impl<DI: WriteOnlyDataCommand, SIZE: DisplaySize> Screen<DI, SIZE> {
+    /// 从任意显示接口创建并初始化指定尺寸和旋转方向的屏幕
+    ///
+    /// 屏幕倒装时使用 `DisplayRotation::Rotate180`
+    pub fn from_interface_with(interface: DI, size: SIZE, rotation: DisplayRotation) -> Result<Self> {
         let mut driver = Ssd1306::new(interface, size, rotation).into_buffered_graphics_mode();
         
         driver.init().map_err(|_| anyhow::anyhow!("Screen init failed"))?;
 
         // 初始化屏幕代码
-        Ok(Self { driver})
+        Ok(Self { driver, dma: None, asleep: false })
     }
 
     // pub fn draw_example(&mut self) -> Result<()> {
@@ -112,9 +615,74 @@ impl<SPI: SpiDevice> Screen<SPI> {
     //     Ok(())
     // }
 
-    // 每次绘制后需要调用 flush 将缓冲区内容显示到屏幕上
+    /// 每次绘制后需要调用 flush 将缓冲区内容显示到屏幕上
+    ///
+    /// 阻塞直到帧数据发送完毕，使用 DMA 总线时会启动传输并等待完成
     pub fn flush(&mut self) -> Result<()> {
+        self.flush_start()?;
+        if let Some(engine) = &self.dma {
+            engine
+                .borrow_mut()
+                .wait()
+                .map_err(|e| anyhow::anyhow!("Screen DMA flush failed: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// 开始刷新，使用 DMA 总线时帧数据排入 DMA 队列后立即返回
+    ///
+    /// 未配置 DMA 时退化为阻塞的 `flush`。传输期间可以继续绘制下一帧；
+    /// 再次刷新或发送命令时会先等待上一次传输完成。
+    pub fn flush_start(&mut self) -> Result<()> {
+        // DMA 总线上这一步只把帧数据复制到 DMA 缓冲区
         self.driver.flush().map_err(|_| anyhow::anyhow!("Screen flush failed"))?;
+        if let Some(engine) = &self.dma {
+            engine
+                .borrow_mut()
+                .start()
+                .map_err(|e| anyhow::anyhow!("Screen DMA flush failed: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// 非阻塞查询上一次 `flush_start` 的传输是否完成，未配置 DMA 时总是返回 true
+    pub fn flush_complete(&mut self) -> bool {
+        self.dma
+            .as_ref()
+            .is_none_or(|engine| engine.borrow_mut().poll())
+    }
+
+    /// 关闭显示以省电并避免长时间显示静态内容造成烧屏
+    ///
+    /// 只发送 SSD1306 的关显示命令，帧缓冲区和显存内容保持不变，
+    /// 休眠期间仍可绘制和 `flush`，`wake` 后显示最新内容
+    pub fn sleep(&mut self) -> Result<()> {
+        self.driver
+            .set_display_on(false)
+            .map_err(|_| anyhow::anyhow!("Screen sleep failed"))?;
+        self.asleep = true;
+        Ok(())
+    }
+
+    /// 重新打开显示
+    pub fn wake(&mut self) -> Result<()> {
+        self.driver
+            .set_display_on(true)
+            .map_err(|_| anyhow::anyhow!("Screen wake failed"))?;
+        self.asleep = false;
+        Ok(())
+    }
+
+    /// 屏幕是否处于休眠状态
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+
+    /// 设置对比度（0~255），数值越小越暗，可用于夜间调暗屏幕
+    pub fn set_contrast(&mut self, level: u8) -> Result<()> {
+        self.driver
+            .set_brightness(Brightness::custom(SCREEN_PRECHARGE, level))
+            .map_err(|_| anyhow::anyhow!("Screen set contrast failed"))?;
         Ok(())
     }
 
@@ -125,18 +693,254 @@ impl<SPI: SpiDevice> Screen<SPI> {
     }
 
     pub fn draw_text(&mut self, text: &str, position: Point) -> Result<()> {
-        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
-        Text::new(text, position, style)
-            .draw(&mut self.driver)
-            .map_err(|_| anyhow::anyhow!("Text draw failed"))?;
-        Ok(())
+        self.draw_text_sized(text, position, FontSize::Small)
     }
 
     pub fn draw_text_big(&mut self, text: &str, position: Point) -> Result<()> {
-        let style = MonoTextStyle::new(&FONT_9X18_BOLD, BinaryColor::On);
-        Text::new(text, position, style)
-            .draw(&mut self.driver)
-            .map_err(|_| anyhow::anyhow!("Text draw failed"))?;
+        self.draw_text_sized(text, position, FontSize::Large)
+    }
+
+    /// 以指定字号绘制文本，支持 '\n' 换行
+    pub fn draw_text_sized(&mut self, text: &str, position: Point, size: FontSize) -> Result<()> {
+        let style = MonoTextStyle::new(size.font(), BinaryColor::On);
+        self.draw_lines(text, position, style)
+    }
+
+    /// 在 `area` 内绘制折线图，纵轴按 `samples` 的最小/最大值自动缩放
+    ///
+    /// 样本均匀分布在整个宽度上，样本少于像素时拉伸；样本多于像素时请先降采样。
+    /// 所有样本相等时在区域中间画一条水平线。
+    pub fn draw_sparkline(&mut self, samples: &[f32], area: Rectangle) -> Result<()> {
+        let points = sparkline_points(samples, area);
+        let style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+        match points.as_slice() {
+            [] => Ok(()),
+            [point] => Pixel(*point, BinaryColor::On)
+                .draw(&mut self.driver)
+                .map_err(|_| anyhow::anyhow!("Sparkline draw failed")),
+            points => Polyline::new(points)
+                .into_styled(style)
+                .draw(&mut self.driver)
+                .map_err(|_| anyhow::anyhow!("Sparkline draw failed")),
+        }
+    }
+
+    /// 以指定字号绘制水平居中的文本，多行文本每行分别居中
+    ///
+    /// `y` 为第一行的基线位置，与 `draw_text_sized` 相同
+    pub fn draw_text_centered(&mut self, text: &str, y: i32, size: FontSize) -> Result<()> {
+        self.draw_text_aligned(text, y, size, HorizontalAlign::Center)
+    }
+
+    /// 以指定字号绘制右对齐的文本，每行的右边缘距屏幕右侧 `right_margin` 像素
+    pub fn draw_text_right(&mut self, text: &str, y: i32, right_margin: i32, size: FontSize) -> Result<()> {
+        self.draw_text_aligned(text, y, size, HorizontalAlign::Right { margin: right_margin })
+    }
+
+    fn draw_text_aligned(&mut self, text: &str, y: i32, size: FontSize, align: HorizontalAlign) -> Result<()> {
+        let style = MonoTextStyle::new(size.font(), BinaryColor::On);
+        let display_width = self.driver.size().width;
+        let positions = line_positions(text, Point::new(0, y), size.font().character_size.height as i32)
+            .map(|(line, pos)| (line, Point::new(align.line_x(line, size, display_width), pos.y)));
+        self.draw_positioned(positions, style)
+    }
+
+    // 按 '\n' 拆分后逐行绘制，每行向下移动一个字符高度
+    fn draw_lines(&mut self, text: &str, position: Point, style: MonoTextStyle<'_, BinaryColor>) -> Result<()> {
+        let line_height = style.font.character_size.height as i32;
+        self.draw_positioned(line_positions(text, position, line_height), style)
+    }
+
+    fn draw_positioned<'t>(
+        &mut self,
+        lines: impl Iterator<Item = (&'t str, Point)>,
+        style: MonoTextStyle<'_, BinaryColor>,
+    ) -> Result<()> {
+        for (line, pos) in lines {
+            Text::new(line, pos, style)
+                .draw(&mut self.driver)
+                .map_err(|_| anyhow::anyhow!("Text draw failed"))?;
+        }
         Ok(())
     }
 }
+
+/// 将多行文本拆分为 (行内容, 起始坐标)，第 n 行的 y 坐标为 `origin.y + n * line_height`
+fn line_positions(text: &str, origin: Point, line_height: i32) -> impl Iterator<Item = (&str, Point)> {
+    text.split('\n')
+        .enumerate()
+        .map(move |(i, line)| (line, Point::new(origin.x, origin.y + i as i32 * line_height)))
+}
+
+/// 将样本映射到 `area` 内的像素坐标，第一个样本在左边缘，最后一个在右边缘，最大值在顶部
+fn sparkline_points(samples: &[f32], area: Rectangle) -> Vec<Point> {
+    if samples.is_empty() || area.size.width == 0 || area.size.height == 0 {
+        return Vec::new();
+    }
+    let left = area.top_left.x;
+    let top = area.top_left.y;
+    let max_dx = area.size.width as i32 - 1;
+    let max_dy = area.size.height as i32 - 1;
+
+    let min = samples.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = samples.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+
+    let last = (samples.len() - 1).max(1) as i32;
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let x = left + i as i32 * max_dx / last;
+            let dy = if range > 0.0 {
+                ((max - value) / range * max_dy as f32).round() as i32
+            } else {
+                max_dy / 2
+            };
+            Point::new(x, top + dy)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_prefers_first_address() {
+        let mut probed = Vec::new();
+        let interface = detect_interface(|address| {
+            probed.push(address);
+            true
+        });
+        assert_eq!(interface, ScreenInterface::I2c { address: 0x3C });
+        // 第一个地址应答后不再继续探测
+        assert_eq!(probed, vec![0x3C]);
+    }
+
+    #[test]
+    fn test_detect_alternate_address() {
+        let mut probed = Vec::new();
+        let interface = detect_interface(|address| {
+            probed.push(address);
+            address == 0x3D
+        });
+        assert_eq!(interface, ScreenInterface::I2c { address: 0x3D });
+        assert_eq!(probed, vec![0x3C, 0x3D]);
+    }
+
+    #[test]
+    fn test_detect_falls_back_to_spi() {
+        let mut probed = Vec::new();
+        let interface = detect_interface(|address| {
+            probed.push(address);
+            false
+        });
+        assert_eq!(interface, ScreenInterface::Spi);
+        assert_eq!(probed, SSD1306_I2C_ADDRESSES.to_vec());
+    }
+
+    #[test]
+    fn test_dma_flush_tracker() {
+        let mut tracker = DmaFlushTracker::new();
+
+        // 没有新数据时不启动传输
+        assert!(!tracker.start());
+        assert!(!tracker.in_flight());
+
+        // 一帧的第一块数据需要清空缓冲区，后续块直接追加
+        assert!(tracker.stage());
+        assert!(!tracker.stage());
+        assert!(tracker.is_staged());
+
+        assert!(tracker.start());
+        assert!(tracker.in_flight());
+        // 传输中重复启动不会再次排队
+        assert!(!tracker.start());
+
+        tracker.complete();
+        assert!(!tracker.in_flight());
+        assert!(!tracker.is_staged());
+
+        // 完成后的下一帧重新开始
+        assert!(tracker.stage());
+    }
+
+    #[test]
+    fn test_frame_bytes_follows_display_size() {
+        assert_eq!(frame_bytes::<DisplaySize128x64>(), 1024);
+        assert_eq!(frame_bytes::<DisplaySize128x32>(), 512);
+    }
+
+    #[test]
+    fn test_font_size_metrics() {
+        assert_eq!(text_width("TEMP", FontSize::Small), 4 * 6);
+        assert_eq!(text_width("TEMP", FontSize::Medium), 4 * 7);
+        assert_eq!(text_width("TEMP", FontSize::Large), 4 * 9);
+        assert_eq!(FontSize::Large.font().character_size.height, 18);
+
+        // 多行文本取最长一行
+        assert_eq!(text_width("AB\nABCD", FontSize::Small), 4 * 6);
+        assert_eq!(text_width("", FontSize::Medium), 0);
+    }
+
+    #[test]
+    fn test_line_positions_single_line() {
+        let lines: Vec<_> = line_positions("TEMP:25.0", Point::new(15, 30), 18).collect();
+        assert_eq!(lines, vec![("TEMP:25.0", Point::new(15, 30))]);
+    }
+
+    #[test]
+    fn test_line_positions_multi_line() {
+        let lines: Vec<_> = line_positions("TEMP:25.0\nHUMD:60.0 %", Point::new(15, 30), 18).collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("TEMP:25.0", Point::new(15, 30)),
+                ("HUMD:60.0 %", Point::new(15, 48)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_positions_empty_line_keeps_spacing() {
+        let lines: Vec<_> = line_positions("A\n\nB", Point::new(0, 10), 10).collect();
+        assert_eq!(lines[2], ("B", Point::new(0, 30)));
+    }
+
+    #[test]
+    fn test_sparkline_points() {
+        let area = Rectangle::new(Point::new(10, 40), Size::new(101, 21));
+
+        // 样本拉伸到整个宽度，最大值在顶部，最小值在底部
+        let points = sparkline_points(&[20.0, 25.0, 30.0], area);
+        assert_eq!(
+            points,
+            vec![Point::new(10, 60), Point::new(60, 50), Point::new(110, 40)]
+        );
+
+        // 所有样本相等时画在中间
+        let points = sparkline_points(&[22.5; 4], area);
+        assert!(points.iter().all(|p| p.y == 50));
+        assert_eq!(points.last().unwrap().x, 110);
+
+        assert_eq!(sparkline_points(&[21.0], area), vec![Point::new(10, 50)]);
+        assert!(sparkline_points(&[], area).is_empty());
+        assert!(sparkline_points(&[1.0, 2.0], Rectangle::zero()).is_empty());
+    }
+
+    #[test]
+    fn test_horizontal_align() {
+        // 6x10 字体 "25.0" 宽 24 像素，9x18 字体宽 36 像素
+        assert_eq!(HorizontalAlign::Center.line_x("25.0", FontSize::Small, 128), 52);
+        assert_eq!(HorizontalAlign::Center.line_x("25.0", FontSize::Large, 128), 46);
+
+        let right = HorizontalAlign::Right { margin: 2 };
+        assert_eq!(right.line_x("25.0", FontSize::Small, 128), 128 - 2 - 24);
+        assert_eq!(right.line_x("25.0", FontSize::Large, 128), 128 - 2 - 36);
+
+        // 超出屏幕宽度时居中文本从左边缘开始
+        let long = "X".repeat(30);
+        assert_eq!(HorizontalAlign::Center.line_x(&long, FontSize::Small, 128), 0);
+    }
+}
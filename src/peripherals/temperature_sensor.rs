@@ -1,9 +1,15 @@
 use crate::data::info_def::InfoSlot;
-use embedded_dht_rs::dht22::Dht22;
+use crate::utils::calculate::{average_recent, MAX_SMOOTHING_WINDOW};
+use crate::utils::circular_queue::CircularQueue;
+use crate::utils::text::truncate_with_ellipsis;
+use embedded_dht_rs::{dht11::Dht11, dht22::Dht22, SensorError, SensorReading};
 use esp_idf_svc::hal::{
     delay::Ets,
-    gpio::{AnyIOPin, PinDriver},
+    gpio::{AnyIOPin, InputOutput, PinDriver},
 };
+use std::ops::RangeInclusive;
+use std::thread::sleep;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,11 +18,164 @@ pub enum TemperatureSensorError {
     Read(String),
     #[error("Pin 配置失败: {0}")]
     PinConfig(#[from] esp_idf_svc::sys::EspError),
+    #[error("读数不合理: 温度 {temperature:.1}°C, 湿度 {humidity:.1}%")]
+    ImplausibleReading { temperature: f32, humidity: f32 },
 }
 
-/// 温度传感器封装，目前支持 DHT22
+/// 传感器型号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorKind {
+    /// 只输出整数读数（1°C / 1% 分辨率），小数部分始终为 0
+    Dht11,
+    /// 0.1°C / 0.1% 分辨率
+    Dht22,
+}
+
+type DhtPin = PinDriver<'static, AnyIOPin, InputOutput>;
+
+/// 按型号分派的底层驱动
+enum DhtDriver {
+    Dht11(Dht11<DhtPin, Ets>),
+    Dht22(Dht22<DhtPin, Ets>),
+}
+
+impl DhtDriver {
+    fn new(pin: DhtPin, kind: SensorKind) -> Self {
+        match kind {
+            SensorKind::Dht11 => DhtDriver::Dht11(Dht11::new(pin, Ets)),
+            SensorKind::Dht22 => DhtDriver::Dht22(Dht22::new(pin, Ets)),
+        }
+    }
+
+    /// 读取并统一为 f32 读数
+    fn read(&mut self) -> Result<SensorReading<f32>, SensorError> {
+        match self {
+            DhtDriver::Dht11(dht) => dht.read().map(|r| SensorReading {
+                temperature: f32::from(r.temperature),
+                humidity: f32::from(r.humidity),
+            }),
+            DhtDriver::Dht22(dht) => dht.read(),
+        }
+    }
+}
+
+/// 温湿度的合理范围，超出范围的读数视为损坏（通常是接线或干扰导致）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorRange {
+    /// 温度范围（°C）
+    pub temperature: RangeInclusive<f32>,
+    /// 相对湿度范围（%）
+    pub humidity: RangeInclusive<f32>,
+}
+
+impl SensorRange {
+    /// 温湿度是否都落在范围内
+    pub fn contains(&self, temperature: f32, humidity: f32) -> bool {
+        self.temperature.contains(&temperature) && self.humidity.contains(&humidity)
+    }
+
+    /// 校验读数，超出范围（包括 NaN）时返回 `ImplausibleReading`
+    pub fn validate(&self, temperature: f32, humidity: f32) -> Result<(), TemperatureSensorError> {
+        if self.contains(temperature, humidity) {
+            Ok(())
+        } else {
+            Err(TemperatureSensorError::ImplausibleReading {
+                temperature,
+                humidity,
+            })
+        }
+    }
+}
+
+impl Default for SensorRange {
+    /// 默认使用 DHT22 的测量范围：-40~80°C，0~100% RH
+    fn default() -> Self {
+        SensorKind::DHT22_RANGE
+    }
+}
+
+impl SensorKind {
+    /// DHT11：0~50°C，20~90% RH
+    pub const DHT11_RANGE: SensorRange = SensorRange {
+        temperature: 0.0..=50.0,
+        humidity: 20.0..=90.0,
+    };
+
+    /// DHT22：-40~80°C，0~100% RH
+    pub const DHT22_RANGE: SensorRange = SensorRange {
+        temperature: -40.0..=80.0,
+        humidity: 0.0..=100.0,
+    };
+
+    /// 该型号的有效测量范围
+    pub fn valid_range(self) -> SensorRange {
+        match self {
+            SensorKind::Dht11 => Self::DHT11_RANGE,
+            SensorKind::Dht22 => Self::DHT22_RANGE,
+        }
+    }
+
+    /// 按该型号的有效测量范围校验读数
+    pub fn validate(self, temperature: f32, humidity: f32) -> Result<(), TemperatureSensorError> {
+        self.valid_range().validate(temperature, humidity)
+    }
+}
+
+/// 驱动错误信息的最大字符数，避免异常的调试输出撑爆日志
+const MAX_ERROR_MESSAGE_CHARS: usize = 128;
+
+/// 最多调用 `attempts` 次 `read`，直到成功为止，两次调用之间等待 `delay`
+///
+/// `attempts` 为 0 时按 1 处理；全部失败时返回最后一次的错误
+fn retry_with_delay<T, E: std::fmt::Display>(
+    attempts: u8,
+    delay: Duration,
+    mut read: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let attempts = attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match read() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < attempts => {
+                log::debug!("第 {attempt}/{attempts} 次读取失败: {e}，{delay:?} 后重试");
+                attempt += 1;
+                sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 校准偏移，读数加上偏移后得到校准值
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Calibration {
+    /// 温度偏移（°C）
+    pub temperature_offset: f32,
+    /// 相对湿度偏移（%）
+    pub humidity_offset: f32,
+}
+
+impl Calibration {
+    /// 应用偏移，湿度结果限制在 0~100%
+    pub fn apply(&self, temperature: f32, humidity: f32) -> (f32, f32) {
+        (
+            temperature + self.temperature_offset,
+            (humidity + self.humidity_offset).clamp(0.0, 100.0),
+        )
+    }
+}
+
+/// 温度传感器封装，支持 DHT11 和 DHT22
 pub struct TemperatureSensor {
-    dht22: Dht22<PinDriver<'static, AnyIOPin, esp_idf_svc::hal::gpio::InputOutput>, Ets>,
+    driver: DhtDriver,
+    kind: SensorKind,
+    /// 读数的合理范围，默认为传感器型号的有效测量范围
+    plausible_range: SensorRange,
+    /// 校准偏移，默认不校准
+    calibration: Calibration,
+    /// 最近的原始读数，用于滑动平均
+    history: CircularQueue<InfoSlot, MAX_SMOOTHING_WINDOW>,
 }
 
 impl TemperatureSensor {
@@ -24,41 +183,151 @@ impl TemperatureSensor {
     /// 
     /// 默认推荐使用 GPIO5 作为 DHT22 数据引脚
     /// 
+    /// 数据引脚需配置为开漏输入输出。参数类型为 `AnyIOPin`，仅输入引脚无法转换为该类型，
+    /// 在编译期即被拒绝；ESP32-C3 的所有 GPIO 均支持输出，不存在仅输入引脚。
+    /// 
     /// # Arguments
     /// * `data_pin` - DHT22 数据引脚
     /// 
     /// # Returns
     /// * `Result<Self, TemperatureSensorError>` - 成功返回传感器实例，失败返回错误
     pub fn from_pin(data_pin: impl Into<AnyIOPin>) -> Result<Self, TemperatureSensorError> {
+        Self::from_pin_with_kind(data_pin, SensorKind::Dht22)
+    }
+
+    /// 从单个 GPIO pin 创建指定型号的温度传感器实例
+    ///
+    /// DHT11 只输出整数温湿度，`read_data` 返回的 InfoSlot 小数部分始终为 0，
+    /// 且有效范围更窄（0~50°C，20~90% RH）。
+    ///
+    /// # Arguments
+    /// * `data_pin` - 传感器数据引脚
+    /// * `kind` - 传感器型号
+    pub fn from_pin_with_kind(
+        data_pin: impl Into<AnyIOPin>,
+        kind: SensorKind,
+    ) -> Result<Self, TemperatureSensorError> {
         // 配置 GPIO pin 为输入输出开漏模式
         let pin: AnyIOPin = data_pin.into();
         let pin = PinDriver::input_output_od(pin)?;
-        let dht22 = Dht22::new(pin, Ets);
-        
-        Ok(Self { dht22 })
+
+        Ok(Self {
+            driver: DhtDriver::new(pin, kind),
+            kind,
+            plausible_range: kind.valid_range(),
+            calibration: Calibration::default(),
+            history: CircularQueue::new(),
+        })
+    }
+
+    /// 设置读数的合理范围，例如室内监测时可收窄到 -10~50°C 以过滤更多异常跳变
+    pub fn with_plausible_range(mut self, range: SensorRange) -> Self {
+        self.plausible_range = range;
+        self
+    }
+
+    /// 设置校准偏移
+    ///
+    /// 偏移会加到读数上：传感器比参考温度计高 1.5°C 时，`temp_offset` 应为 `-1.5`。
+    /// 校准后的湿度限制在 0~100%。
+    pub fn with_calibration(mut self, temp_offset: f32, hum_offset: f32) -> Self {
+        self.set_calibration(temp_offset, hum_offset);
+        self
+    }
+
+    /// 运行时修改校准偏移，符号约定同 `with_calibration`
+    pub fn set_calibration(&mut self, temp_offset: f32, hum_offset: f32) {
+        self.calibration = Calibration {
+            temperature_offset: temp_offset,
+            humidity_offset: hum_offset,
+        };
+    }
+
+    /// 当前的校准偏移
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    /// 传感器型号
+    pub fn kind(&self) -> SensorKind {
+        self.kind
+    }
+
+    /// 当前使用的读数合理范围
+    pub fn plausible_range(&self) -> &SensorRange {
+        &self.plausible_range
     }
 
     /// 读取传感器数据并返回 InfoSlot
+    ///
+    /// 校验和由驱动检查；超出合理范围的读数返回 `ImplausibleReading`，不会构造 InfoSlot。
+    /// 合理范围针对原始读数校验，通过后再应用校准偏移
     /// 
     /// # Returns
     /// * `Result<InfoSlot, TemperatureSensorError>` - 成功返回温湿度数据，失败返回错误
     pub fn read_data(&mut self) -> Result<InfoSlot, TemperatureSensorError> {
-        match self.dht22.read() {
-            Ok(reading) => {
-                let info_slot = InfoSlot::new_from_f32(reading.temperature, reading.humidity);
-                log::debug!(
-                    "传感器读取成功: 温度 {:.1}°C, 湿度 {:.1}%",
-                    reading.temperature,
-                    reading.humidity
-                );
-                Ok(info_slot)
-            }
-            Err(e) => {
-                let error_msg = format!("DHT22 读取失败: {e:?}");
-                log::error!("{error_msg}");
-                Err(TemperatureSensorError::Read(error_msg))
-            }
+        self.read_once().inspect_err(|e| log::error!("{e}"))
+    }
+
+    /// 读取传感器数据，失败时最多尝试 `attempts` 次，每次之间等待 `delay`
+    ///
+    /// DHT22 对时序敏感，单次读取经常因时序偏差失败，重试可以避免丢失整个采样周期。
+    /// DHT22 两次读取之间至少需要间隔 2 秒，`delay` 过短时重试大概率仍会失败。
+    /// 每次失败以 debug 级别记录，全部失败时返回最后一次的错误。
+    ///
+    /// # Arguments
+    /// * `attempts` - 最多尝试次数，0 按 1 处理
+    /// * `delay` - 两次尝试之间的等待时间
+    pub fn read_data_retry(
+        &mut self,
+        attempts: u8,
+        delay: Duration,
+    ) -> Result<InfoSlot, TemperatureSensorError> {
+        retry_with_delay(attempts, delay, || self.read_once())
+            .inspect_err(|e| log::error!("{e}"))
+    }
+
+    /// 读取一次传感器数据，不记录错误日志
+    fn read_once(&mut self) -> Result<InfoSlot, TemperatureSensorError> {
+        #[cfg(feature = "fault-inject")]
+        if crate::utils::fault_inject::take(crate::utils::fault_inject::Fault::SensorRead) {
+            return Err(TemperatureSensorError::Read("注入的传感器读取故障".to_string()));
         }
+
+        let reading = self.driver.read().map_err(|e| {
+            let detail = format!("{e:?}");
+            TemperatureSensorError::Read(format!(
+                "{:?} 读取失败: {}",
+                self.kind,
+                truncate_with_ellipsis(&detail, MAX_ERROR_MESSAGE_CHARS)
+            ))
+        })?;
+        self.plausible_range
+            .validate(reading.temperature, reading.humidity)?;
+        let (temperature, humidity) =
+            self.calibration.apply(reading.temperature, reading.humidity);
+        log::debug!(
+            "传感器读取成功: 温度 {temperature:.1}°C, 湿度 {humidity:.1}%（原始 {:.1}°C, {:.1}%）",
+            reading.temperature,
+            reading.humidity
+        );
+        Ok(InfoSlot::new_from_f32(temperature, humidity))
+    }
+
+    /// 读取传感器数据并返回最近 `window` 次读数的滑动平均
+    ///
+    /// DHT22 相邻两次读数之间约有 ±0.5°C 的噪声，平均后更适合显示和存储。
+    /// 前 `window - 1` 次调用返回已有读数的平均值；读取失败或读数不合理时
+    /// 返回错误，且不计入平均。
+    ///
+    /// # Arguments
+    /// * `window` - 窗口长度，0 按 1 处理，最大为 `MAX_SMOOTHING_WINDOW`
+    pub fn read_smoothed(&mut self, window: usize) -> Result<InfoSlot, TemperatureSensorError> {
+        let slot = self.read_data()?;
+        self.history.push_overwrite(slot);
+        // 刚刚写入了一个读数，平均值必然存在
+        let (temperature, humidity) = average_recent(&self.history, window).unwrap_or(slot.into());
+        Ok(InfoSlot::new_from_f32(temperature, humidity))
     }
 
     // /// 尝试读取传感器数据，失败时返回 None 而不是错误
@@ -87,5 +356,98 @@ impl TemperatureSensor {
     // }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dht11_range() {
+        assert!(SensorKind::Dht11.validate(25.0, 50.0).is_ok());
+        assert!(SensorKind::Dht11.validate(0.0, 20.0).is_ok());
 
+        // DHT11 无法测量零下温度和 90% 以上湿度
+        assert!(matches!(
+            SensorKind::Dht11.validate(-5.0, 50.0),
+            Err(TemperatureSensorError::ImplausibleReading { temperature, .. }) if temperature == -5.0
+        ));
+        assert!(SensorKind::Dht11.validate(25.0, 95.0).is_err());
+    }
+
+    #[test]
+    fn test_dht22_range() {
+        assert!(SensorKind::Dht22.validate(-40.0, 0.0).is_ok());
+        assert!(SensorKind::Dht22.validate(80.0, 100.0).is_ok());
+
+        assert!(matches!(
+            SensorKind::Dht22.validate(85.0, 50.0),
+            Err(TemperatureSensorError::ImplausibleReading { humidity, .. }) if humidity == 50.0
+        ));
+        assert!(SensorKind::Dht22.validate(25.0, 100.1).is_err());
+        assert!(SensorKind::Dht22.validate(f32::NAN, 50.0).is_err());
+    }
+
+    #[test]
+    fn test_retry_with_delay() {
+        // 第三次成功
+        let mut calls = 0;
+        let result: Result<u8, String> = retry_with_delay(3, Duration::ZERO, || {
+            calls += 1;
+            if calls < 3 {
+                Err(format!("失败 {calls}"))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result, Ok(3));
 
+        // 全部失败时返回最后一次的错误
+        let mut calls = 0;
+        let result: Result<(), String> = retry_with_delay(2, Duration::ZERO, || {
+            calls += 1;
+            Err(format!("失败 {calls}"))
+        });
+        assert_eq!(result, Err("失败 2".to_string()));
+
+        // attempts 为 0 时仍尝试一次
+        let mut calls = 0;
+        let _: Result<(), String> = retry_with_delay(0, Duration::ZERO, || {
+            calls += 1;
+            Err(String::new())
+        });
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_calibration() {
+        assert_eq!(Calibration::default().apply(25.0, 50.0), (25.0, 50.0));
+
+        // 传感器偏高 1.5°C，偏移为负
+        let calibration = Calibration {
+            temperature_offset: -1.5,
+            humidity_offset: 3.0,
+        };
+        assert_eq!(calibration.apply(25.0, 50.0), (23.5, 53.0));
+
+        // 湿度限制在 0~100%
+        assert_eq!(calibration.apply(25.0, 99.0).1, 100.0);
+        let dry = Calibration {
+            temperature_offset: 0.0,
+            humidity_offset: -5.0,
+        };
+        assert_eq!(dry.apply(25.0, 2.0).1, 0.0);
+    }
+
+    #[test]
+    fn test_custom_plausible_range() {
+        assert_eq!(SensorRange::default(), SensorKind::DHT22_RANGE);
+
+        let indoor = SensorRange {
+            temperature: -10.0..=50.0,
+            humidity: 5.0..=100.0,
+        };
+        assert!(indoor.validate(22.0, 45.0).is_ok());
+        // 0% 湿度和 80°C 在默认范围内，但对室内环境不合理
+        assert!(indoor.validate(22.0, 0.0).is_err());
+        assert!(indoor.validate(80.0, 45.0).is_err());
+    }
+}
@@ -1,10 +1,16 @@
-use anyhow::{bail, Result};
+use crate::utils::watchdog;
+use anyhow::{anyhow, bail, Result};
 use esp_idf_svc::{
     eventloop::EspSystemEventLoop,
     hal::peripheral,
-    wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi},
+    ipv4,
+    netif::{EspNetif, NetifConfiguration, NetifStack},
+    ping::{self, EspPing},
+    wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi, WifiDriver},
 };
-use log::info;
+use log::{info, warn};
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
 
 /// WiFi 配置构建器
 /// 
@@ -16,14 +22,18 @@ use log::info;
 /// - 网络状态变化事件
 /// 
 /// 它是 ESP32 异步事件处理的核心机制，WiFi、蓝牙、以太网等模块都依赖它。
+///
+/// 可配置多个网络，`build` 时按添加顺序（优先级）尝试扫描到的网络，直到连接成功。
 pub struct WifiBuilder<'a> {
-    ssid: &'a str,
-    password: &'a str,
+    /// (SSID, 密码)，按优先级排列
+    networks: Vec<(&'a str, &'a str)>,
     auth_method: Option<AuthMethod>,
     channel: Option<u8>,
     scan_for_channel: bool,
     auto_connect: bool,
     bssid: Option<[u8; 6]>,
+    /// 静态 IP 配置，为 `None` 时使用 DHCP
+    static_ip: Option<ipv4::ClientSettings>,
 }
 
 impl<'a> WifiBuilder<'a> {
@@ -34,58 +44,103 @@ impl<'a> WifiBuilder<'a> {
     /// - `password`: WiFi 密码（如果为空，将使用无认证方式）
     pub fn new(ssid: &'a str, password: &'a str) -> Self {
         Self {
-            ssid,
-            password,
+            networks: vec![(ssid, password)],
             auth_method: None,
             channel: None,
             scan_for_channel: true,
             auto_connect: true,
             bssid: None,
+            static_ip: None,
         }
     }
 
-    // /// 设置认证方法
-    // ///
-    // /// 如果不设置，将根据密码自动选择：
-    // /// - 密码为空：AuthMethod::None
-    // /// - 密码不为空：AuthMethod::WPA2Personal
-    // pub fn auth_method(mut self, auth_method: AuthMethod) -> Self {
-    //     self.auth_method = Some(auth_method);
-    //     self
-    // }
-
-    // /// 设置指定的 WiFi 频道
-    // ///
-    // /// 如果设置了频道，将不会进行扫描
-    // pub fn channel(mut self, channel: u8) -> Self {
-    //     self.channel = Some(channel);
-    //     self.scan_for_channel = false;
-    //     self
-    // }
-
-    // /// 设置是否扫描并自动选择频道
-    // ///
-    // /// 默认为 true
-    // pub fn scan_for_channel(mut self, scan: bool) -> Self {
-    //     self.scan_for_channel = scan;
-    //     self
-    // }
-
-    // /// 设置是否自动连接
-    // ///
-    // /// 默认为 true。如果设置为 false，需要手动调用连接方法
-    // pub fn auto_connect(mut self, auto_connect: bool) -> Self {
-    //     self.auto_connect = auto_connect;
-    //     self
-    // }
-
-    // /// 设置 BSSID（MAC 地址）
-    // ///
-    // /// 用于连接到特定的接入点
-    // pub fn bssid(mut self, bssid: [u8; 6]) -> Self {
-    //     self.bssid = Some(bssid);
-    //     self
-    // }
+    /// 添加一个备用网络，优先级低于之前添加的网络
+    pub fn add_network(mut self, ssid: &'a str, password: &'a str) -> Self {
+        self.networks.push((ssid, password));
+        self
+    }
+
+    /// 使用静态 IP 代替 DHCP，省去每次启动时的 DHCP 协商
+    ///
+    /// # 参数
+    /// - `ip`: 本机地址
+    /// - `gateway`: 网关地址
+    /// - `mask`: 子网掩码，例如 `255.255.255.0`
+    /// - `dns`: DNS 服务器，为 `None` 时不设置
+    ///
+    /// 掩码不连续，或 `ip` 与 `gateway` 不在同一子网时返回错误
+    pub fn static_ip(
+        mut self,
+        ip: Ipv4Addr,
+        gateway: Ipv4Addr,
+        mask: Ipv4Addr,
+        dns: Option<Ipv4Addr>,
+    ) -> Result<Self> {
+        let Ok(mask) = ipv4::Mask::try_from(mask) else {
+            bail!("Invalid subnet mask {mask}");
+        };
+        if !in_same_subnet(ip, gateway, mask.0) {
+            bail!("Static IP {ip} is not in the subnet of gateway {gateway}/{mask}");
+        }
+        self.static_ip = Some(ipv4::ClientSettings {
+            ip,
+            subnet: ipv4::Subnet { gateway, mask },
+            dns,
+            secondary_dns: None,
+        });
+        Ok(self)
+    }
+
+    /// 设置认证方法
+    ///
+    /// 如果不设置，将根据密码自动选择：
+    /// - 密码为空：AuthMethod::None
+    /// - 密码不为空：AuthMethod::WPA2Personal
+    pub fn auth_method(mut self, auth_method: AuthMethod) -> Self {
+        self.auth_method = Some(auth_method);
+        self
+    }
+
+    /// 设置指定的 WiFi 频道
+    ///
+    /// 如果设置了频道，将不会进行扫描，所有网络都使用该频道；可用于连接隐藏网络
+    ///
+    /// # 示例
+    /// ```no_run
+    /// let wifi = WifiBuilder::new("lab", "password")
+    ///     .channel(6)
+    ///     .auto_connect(false)
+    ///     .build(peripherals.modem, sysloop)?;
+    /// ```
+    pub fn channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel);
+        self.scan_for_channel = false;
+        self
+    }
+
+    /// 设置是否扫描并自动选择频道
+    ///
+    /// 默认为 true
+    pub fn scan_for_channel(mut self, scan: bool) -> Self {
+        self.scan_for_channel = scan;
+        self
+    }
+
+    /// 设置是否自动连接
+    ///
+    /// 默认为 true。如果设置为 false，只配置优先级最高的网络，需要手动调用连接方法
+    pub fn auto_connect(mut self, auto_connect: bool) -> Self {
+        self.auto_connect = auto_connect;
+        self
+    }
+
+    /// 设置 BSSID（MAC 地址）
+    ///
+    /// 用于连接到特定的接入点，对所有配置的网络生效
+    pub fn bssid(mut self, bssid: [u8; 6]) -> Self {
+        self.bssid = Some(bssid);
+        self
+    }
 
     /// 构建并初始化 WiFi 连接
     ///
@@ -97,23 +152,31 @@ impl<'a> WifiBuilder<'a> {
         modem: impl peripheral::Peripheral<P = esp_idf_svc::hal::modem::Modem> + 'static,
         sysloop: EspSystemEventLoop,
     ) -> Result<Box<EspWifi<'static>>> {
-        // 验证 SSID
-        if self.ssid.is_empty() {
+        // 验证 SSID 和密码，避免连接时才发现无法转换为 WiFi 配置
+        if self.networks.iter().any(|(ssid, _)| ssid.is_empty()) {
             bail!("Missing WiFi name")
         }
+        for &(ssid, password) in &self.networks {
+            validate_credentials(ssid, password)?;
+        }
 
-        // 确定认证方法
-        let auth_method = if let Some(method) = self.auth_method {
-            method
-        } else if self.password.is_empty() {
-            info!("Wifi password is empty, using AuthMethod::None");
-            AuthMethod::None
+        // 创建 WiFi 实例，配置了静态 IP 时使用固定地址的 STA 网络接口
+        let mut esp_wifi = if let Some(settings) = self.static_ip {
+            info!("Using static IP {}", settings.ip);
+            let sta_netif = EspNetif::new_with_conf(&NetifConfiguration {
+                ip_configuration: Some(ipv4::Configuration::Client(
+                    ipv4::ClientConfiguration::Fixed(settings),
+                )),
+                ..NetifConfiguration::wifi_default_client()
+            })?;
+            EspWifi::wrap_all(
+                WifiDriver::new(modem, sysloop.clone(), None)?,
+                sta_netif,
+                EspNetif::new(NetifStack::Ap)?,
+            )?
         } else {
-            AuthMethod::WPA2Personal
+            EspWifi::new(modem, sysloop.clone(), None)?
         };
-
-        // 创建 WiFi 实例
-        let mut esp_wifi = EspWifi::new(modem, sysloop.clone(), None)?;
         let mut wifi = BlockingWifi::wrap(&mut esp_wifi, sysloop)?;
 
         // 设置初始配置
@@ -122,59 +185,419 @@ impl<'a> WifiBuilder<'a> {
         info!("Starting wifi...");
         wifi.start()?;
 
-        // 扫描并查找频道（如果需要）
-        let channel = if let Some(ch) = self.channel {
-            Some(ch)
+        // 只扫描一次，确定各网络是否存在及其频道
+        let ssids: Vec<&str> = self.networks.iter().map(|(ssid, _)| *ssid).collect();
+        let attempts = if let Some(ch) = self.channel {
+            ssids.iter().map(|_| Some(ch)).enumerate().collect()
         } else if self.scan_for_channel {
             info!("Scanning for WiFi networks...");
             let ap_infos = wifi.scan()?;
-            let ours = ap_infos.into_iter().find(|a| a.ssid == self.ssid);
-
-            if let Some(ours) = ours {
-                info!(
-                    "Found configured access point {} on channel {}",
-                    self.ssid, ours.channel
-                );
-                Some(ours.channel)
-            } else {
-                info!(
-                    "Configured access point {} not found during scanning, will go with unknown channel",
-                    self.ssid
+            let found: Vec<(&str, u8)> = ap_infos
+                .iter()
+                .map(|ap| (ap.ssid.as_str(), ap.channel))
+                .collect();
+            let attempts = plan_attempts(&ssids, Some(&found));
+            if attempts.iter().all(|(_, channel)| channel.is_none()) {
+                warn!(
+                    "None of the configured access points {ssids:?} found during scanning, will try them all with unknown channel"
                 );
-                None
             }
+            attempts
         } else {
-            None
+            plan_attempts(&ssids, None)
         };
 
-        // 配置 WiFi 客户端
-        wifi.set_configuration(&Configuration::Client(ClientConfiguration {
-            ssid: self
-                .ssid
-                .try_into()
-                .expect("Could not parse the given SSID into WiFi config"),
-            password: self
-                .password
-                .try_into()
-                .expect("Could not parse the given password into WiFi config"),
-            channel,
-            auth_method,
-            bssid: self.bssid,
-            ..Default::default()
-        }))?;
-
-        // 自动连接（如果启用）
-        if self.auto_connect {
-            info!("Connecting to wifi...");
-            wifi.connect()?;
+        for (index, channel) in attempts {
+            watchdog::feed();
+            let (ssid, password) = self.networks[index];
+            if let Some(ch) = channel {
+                info!("Found configured access point {ssid} on channel {ch}");
+            }
 
-            info!("Waiting for DHCP lease...");
-            wifi.wait_netif_up()?;
+            // 配置 WiFi 客户端
+            wifi.set_configuration(&Configuration::Client(ClientConfiguration {
+                // 长度已在开头校验，这里不会失败
+                ssid: ssid
+                    .try_into()
+                    .map_err(|_| anyhow!("Could not parse SSID {ssid:?} into WiFi config"))?,
+                password: password
+                    .try_into()
+                    .map_err(|_| anyhow!("Could not parse password for {ssid:?} into WiFi config"))?,
+                channel,
+                auth_method: self.auth_method_for(password),
+                bssid: self.bssid,
+                ..Default::default()
+            }))?;
+
+            // 未启用自动连接时只配置优先级最高的网络
+            if !self.auto_connect {
+                info!("Configured wifi {ssid}, auto connect disabled");
+                break;
+            }
+
+            info!("Connecting to wifi {ssid}...");
+            if let Err(e) = wifi.connect() {
+                warn!("Failed to connect to {ssid}: {e}");
+                continue;
+            }
+
+            // 静态 IP 无需等待 DHCP 分配地址
+            if self.static_ip.is_none() {
+                info!("Waiting for DHCP lease...");
+                if let Err(e) = wifi.wait_netif_up() {
+                    warn!("Network interface on {ssid} did not come up: {e}");
+                    let _ = wifi.disconnect();
+                    continue;
+                }
+            }
 
             let ip_info = wifi.wifi().sta_netif().get_ip_info()?;
-            info!("Wifi DHCP info: {ip_info:?}");
+            info!("Connected to wifi {ssid}, IP info: {ip_info:?}");
+            drop(wifi);
+            return Ok(Box::new(esp_wifi));
         }
 
+        if self.auto_connect {
+            bail!("Could not connect to any configured WiFi network: {ssids:?}");
+        }
+
+        drop(wifi);
         Ok(Box::new(esp_wifi))
     }
-}
\ No newline at end of file
+}
+
+impl WifiBuilder<'_> {
+    /// 确定认证方法
+    ///
+    /// 如果未设置，将根据密码自动选择：
+    /// - 密码为空：AuthMethod::None
+    /// - 密码不为空：AuthMethod::WPA2Personal
+    fn auth_method_for(&self, password: &str) -> AuthMethod {
+        if let Some(method) = self.auth_method {
+            method
+        } else if password.is_empty() {
+            info!("Wifi password is empty, using AuthMethod::None");
+            AuthMethod::None
+        } else {
+            AuthMethod::WPA2Personal
+        }
+    }
+}
+
+/// SSID 的最大字节数（802.11 规定）
+const MAX_SSID_LEN: usize = 32;
+
+/// 密码的最大字节数，WPA2 PSK 为 64 位十六进制或 8-63 字节的口令
+const MAX_PASSWORD_LEN: usize = 64;
+
+/// 检查 SSID 和密码能否放入 `ClientConfiguration`，出错信息中包含对应的 SSID
+fn validate_credentials(ssid: &str, password: &str) -> Result<()> {
+    if ssid.len() > MAX_SSID_LEN {
+        bail!("WiFi name {ssid:?} is too long: {} bytes, at most {MAX_SSID_LEN}", ssid.len());
+    }
+    if password.len() > MAX_PASSWORD_LEN {
+        bail!(
+            "WiFi password for {ssid:?} is too long: {} bytes, at most {MAX_PASSWORD_LEN}",
+            password.len()
+        );
+    }
+    Ok(())
+}
+
+/// 按优先级确定连接尝试顺序，返回 (网络下标, 频道)
+///
+/// - `scanned` 为 `None`（未扫描）时按顺序尝试所有网络，频道未知
+/// - 只尝试扫描结果中存在的网络，并使用扫描到的频道
+/// - 扫描结果中一个都没有时（例如隐藏网络）按顺序尝试所有网络，频道未知
+fn plan_attempts(ssids: &[&str], scanned: Option<&[(&str, u8)]>) -> Vec<(usize, Option<u8>)> {
+    let all_unknown = || (0..ssids.len()).map(|i| (i, None)).collect();
+    let Some(scanned) = scanned else {
+        return all_unknown();
+    };
+
+    let found: Vec<(usize, Option<u8>)> = ssids
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ssid)| {
+            scanned
+                .iter()
+                .find(|(name, _)| name == ssid)
+                .map(|&(_, channel)| (i, Some(channel)))
+        })
+        .collect();
+    if found.is_empty() {
+        all_unknown()
+    } else {
+        found
+    }
+}
+
+/// `ip` 与 `gateway` 在前缀长度为 `prefix` 的子网中是否属于同一网段
+fn in_same_subnet(ip: Ipv4Addr, gateway: Ipv4Addr, prefix: u8) -> bool {
+    let mask = u32::MAX.checked_shl(32 - prefix.min(32) as u32).unwrap_or(0);
+    u32::from(ip) & mask == u32::from(gateway) & mask
+}
+
+/// 链路存活检测配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LivenessConfig {
+    /// 检测间隔
+    pub interval: Duration,
+    /// 连续失败多少次后强制重连
+    pub max_failures: u32,
+}
+
+impl Default for LivenessConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            max_failures: 3,
+        }
+    }
+}
+
+/// 单次存活检测后的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LivenessAction {
+    /// 网关可达
+    Healthy,
+    /// 检测失败，尚未达到重连阈值
+    Failed { consecutive: u32 },
+    /// 连续失败达到阈值，需要重连
+    Reconnect,
+}
+
+/// WiFi 链路存活检测
+///
+/// 事件驱动的重连无法发现"已关联但没有流量"的静默故障。
+/// 该检测器定期 ping 网关，连续失败 `max_failures` 次后断开并重新连接，与断开事件无关。
+/// 需要在主循环中周期性调用 `check`，未到检测时间时立即返回。
+pub struct LivenessMonitor {
+    config: LivenessConfig,
+    consecutive_failures: u32,
+    last_check: Option<Instant>,
+}
+
+impl LivenessMonitor {
+    pub fn new(config: LivenessConfig) -> Self {
+        Self {
+            config,
+            consecutive_failures: 0,
+            last_check: None,
+        }
+    }
+
+    /// 在 `now` 时刻是否应当进行检测
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.last_check
+            .is_none_or(|last| now.duration_since(last) >= self.config.interval)
+    }
+
+    /// 记录一次检测结果并返回需要采取的动作，返回 `Reconnect` 后计数清零
+    pub fn record(&mut self, healthy: bool) -> LivenessAction {
+        if healthy {
+            self.consecutive_failures = 0;
+            return LivenessAction::Healthy;
+        }
+
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= self.config.max_failures {
+            self.consecutive_failures = 0;
+            LivenessAction::Reconnect
+        } else {
+            LivenessAction::Failed {
+                consecutive: self.consecutive_failures,
+            }
+        }
+    }
+
+    /// 到达检测时间时 ping 网关，连续失败达到阈值时重新连接 WiFi
+    ///
+    /// 未到检测时间时返回 `None`
+    pub fn check(&mut self, wifi: &mut EspWifi<'static>) -> Option<LivenessAction> {
+        #[cfg(feature = "fault-inject")]
+        if crate::utils::fault_inject::take(crate::utils::fault_inject::Fault::WifiDrop) {
+            if let Err(e) = wifi.disconnect() {
+                warn!("注入 WiFi 断开失败: {e}");
+            }
+        }
+
+        let now = Instant::now();
+        if !self.is_due(now) {
+            return None;
+        }
+        self.last_check = Some(now);
+
+        let action = self.record(ping_gateway(wifi));
+        match action {
+            LivenessAction::Healthy => {}
+            LivenessAction::Failed { consecutive } => {
+                warn!(
+                    "网关 ping 失败 ({consecutive}/{})",
+                    self.config.max_failures
+                );
+            }
+            LivenessAction::Reconnect => {
+                warn!(
+                    "网关连续 {} 次不可达，强制重新连接 WiFi",
+                    self.config.max_failures
+                );
+                if let Err(e) = wifi.disconnect().and_then(|_| wifi.connect()) {
+                    warn!("WiFi 重新连接失败: {e}");
+                }
+            }
+        }
+        Some(action)
+    }
+}
+
+/// 读取当前连接的接入点信号强度 (dBm)，未连接时返回错误
+pub fn rssi(wifi: &EspWifi<'_>) -> Result<i8> {
+    if !wifi.is_connected()? {
+        bail!("WiFi is not connected");
+    }
+    let mut ap_info = esp_idf_svc::sys::wifi_ap_record_t::default();
+    esp_idf_svc::sys::esp!(unsafe { esp_idf_svc::sys::esp_wifi_sta_get_ap_info(&mut ap_info) })?;
+    Ok(ap_info.rssi)
+}
+
+/// 将信号强度换算为 0~4 格信号，用于屏幕显示
+pub fn rssi_bars(rssi: i8) -> u8 {
+    match rssi {
+        -55.. => 4,
+        -66..=-56 => 3,
+        -77..=-67 => 2,
+        -88..=-78 => 1,
+        _ => 0,
+    }
+}
+
+/// ping 一次当前网关，收到任意回复即视为可达
+fn ping_gateway(wifi: &EspWifi<'static>) -> bool {
+    let gateway = match wifi.sta_netif().get_ip_info() {
+        Ok(ip_info) => ip_info.subnet.gateway,
+        Err(e) => {
+            warn!("获取网关地址失败: {e}");
+            return false;
+        }
+    };
+
+    let conf = ping::Configuration {
+        count: 3,
+        interval: Duration::from_millis(200),
+        timeout: Duration::from_secs(1),
+        ..Default::default()
+    };
+    match EspPing::default().ping(gateway, &conf) {
+        Ok(summary) => summary.received > 0,
+        Err(e) => {
+            warn!("ping 网关 {gateway} 失败: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_after_consecutive_failures() {
+        let mut monitor = LivenessMonitor::new(LivenessConfig {
+            interval: Duration::from_secs(10),
+            max_failures: 3,
+        });
+
+        assert_eq!(monitor.record(false), LivenessAction::Failed { consecutive: 1 });
+        assert_eq!(monitor.record(false), LivenessAction::Failed { consecutive: 2 });
+        assert_eq!(monitor.record(false), LivenessAction::Reconnect);
+
+        // 重连后重新计数
+        assert_eq!(monitor.record(false), LivenessAction::Failed { consecutive: 1 });
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let mut monitor = LivenessMonitor::new(LivenessConfig {
+            interval: Duration::from_secs(10),
+            max_failures: 2,
+        });
+
+        assert_eq!(monitor.record(false), LivenessAction::Failed { consecutive: 1 });
+        assert_eq!(monitor.record(true), LivenessAction::Healthy);
+        assert_eq!(monitor.record(false), LivenessAction::Failed { consecutive: 1 });
+        assert_eq!(monitor.record(false), LivenessAction::Reconnect);
+    }
+
+    #[test]
+    fn test_plan_attempts() {
+        let ssids = ["home", "lab", "phone"];
+
+        // 按配置的优先级排序，不按扫描结果的顺序
+        let scanned = [("lab", 6), ("other", 1), ("home", 11)];
+        assert_eq!(
+            plan_attempts(&ssids, Some(&scanned)),
+            vec![(0, Some(11)), (1, Some(6))]
+        );
+
+        // 一个都没扫描到时尝试全部网络
+        let scanned = [("other", 1)];
+        assert_eq!(
+            plan_attempts(&ssids, Some(&scanned)),
+            vec![(0, None), (1, None), (2, None)]
+        );
+        assert_eq!(plan_attempts(&ssids, Some(&[])).len(), 3);
+
+        // 未扫描
+        assert_eq!(plan_attempts(&["home"], None), vec![(0, None)]);
+    }
+
+    #[test]
+    fn test_validate_credentials() {
+        assert!(validate_credentials("home", "password").is_ok());
+        assert!(validate_credentials(&"s".repeat(32), &"p".repeat(64)).is_ok());
+
+        let err = validate_credentials(&"s".repeat(33), "password").unwrap_err();
+        assert!(err.to_string().contains(&"s".repeat(33)));
+
+        let err = validate_credentials("home", &"p".repeat(65)).unwrap_err();
+        assert!(err.to_string().contains("\"home\""));
+    }
+
+    #[test]
+    fn test_in_same_subnet() {
+        let gateway = Ipv4Addr::new(192, 168, 1, 1);
+        assert!(in_same_subnet(Ipv4Addr::new(192, 168, 1, 50), gateway, 24));
+        assert!(!in_same_subnet(Ipv4Addr::new(192, 168, 2, 50), gateway, 24));
+        assert!(in_same_subnet(Ipv4Addr::new(192, 168, 2, 50), gateway, 16));
+
+        // 边界前缀长度
+        assert!(in_same_subnet(Ipv4Addr::new(10, 0, 0, 1), gateway, 0));
+        assert!(!in_same_subnet(Ipv4Addr::new(192, 168, 1, 2), gateway, 32));
+    }
+
+    #[test]
+    fn test_rssi_bars() {
+        assert_eq!(rssi_bars(-40), 4);
+        assert_eq!(rssi_bars(-55), 4);
+        assert_eq!(rssi_bars(-56), 3);
+        assert_eq!(rssi_bars(-70), 2);
+        assert_eq!(rssi_bars(-88), 1);
+        assert_eq!(rssi_bars(-89), 0);
+        assert_eq!(rssi_bars(i8::MIN), 0);
+    }
+
+    #[test]
+    fn test_is_due_respects_interval() {
+        let mut monitor = LivenessMonitor::new(LivenessConfig {
+            interval: Duration::from_secs(10),
+            max_failures: 3,
+        });
+        let start = Instant::now();
+        assert!(monitor.is_due(start));
+
+        monitor.last_check = Some(start);
+        assert!(!monitor.is_due(start + Duration::from_secs(9)));
+        assert!(monitor.is_due(start + Duration::from_secs(10)));
+    }
+}
@@ -1 +1,4 @@
+pub mod alarm;
+pub mod http;
+pub mod mqtt;
 pub mod ntp;
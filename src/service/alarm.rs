@@ -0,0 +1,248 @@
+//! 温湿度阈值报警
+//!
+//! 每次读取后调用 `AlarmMonitor::evaluate`，读数越过阈值时产生 `Raised` 事件，
+//! 回到阈值内侧 `clear_margin` 以外时产生 `Cleared` 事件。
+//! 清除需要额外越过回差，读数在阈值附近抖动时不会反复触发。
+
+use crate::data::info_def::InfoSlot;
+use core::fmt;
+
+/// 报警阈值配置，阈值为 `None` 时不检查该项
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlarmConfig {
+    /// 温度上限（°C）
+    pub temp_high: Option<f32>,
+    /// 温度下限（°C）
+    pub temp_low: Option<f32>,
+    /// 湿度上限（%）
+    pub hum_high: Option<f32>,
+    /// 湿度下限（%）
+    pub hum_low: Option<f32>,
+    /// 清除报警所需的回差，温度按 °C、湿度按 % 计
+    pub clear_margin: f32,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self {
+            temp_high: Some(35.0),
+            temp_low: Some(5.0),
+            hum_high: Some(80.0),
+            hum_low: Some(20.0),
+            clear_margin: 1.0,
+        }
+    }
+}
+
+impl AlarmConfig {
+    /// 去掉湿度阈值，用于不测湿度的传感器（如 DS18B20，湿度固定为 0%）
+    pub const fn without_humidity(self) -> Self {
+        Self {
+            hum_high: None,
+            hum_low: None,
+            ..self
+        }
+    }
+}
+
+/// 报警类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlarmKind {
+    TempHigh,
+    TempLow,
+    HumHigh,
+    HumLow,
+}
+
+impl AlarmKind {
+    const ALL: [AlarmKind; 4] = [
+        AlarmKind::TempHigh,
+        AlarmKind::TempLow,
+        AlarmKind::HumHigh,
+        AlarmKind::HumLow,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn is_high(self) -> bool {
+        matches!(self, AlarmKind::TempHigh | AlarmKind::HumHigh)
+    }
+
+    fn threshold(self, config: &AlarmConfig) -> Option<f32> {
+        match self {
+            AlarmKind::TempHigh => config.temp_high,
+            AlarmKind::TempLow => config.temp_low,
+            AlarmKind::HumHigh => config.hum_high,
+            AlarmKind::HumLow => config.hum_low,
+        }
+    }
+
+    fn value(self, slot: &InfoSlot) -> f32 {
+        match self {
+            AlarmKind::TempHigh | AlarmKind::TempLow => slot.get_temperature(),
+            AlarmKind::HumHigh | AlarmKind::HumLow => slot.get_humidity(),
+        }
+    }
+}
+
+impl fmt::Display for AlarmKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AlarmKind::TempHigh => "温度过高",
+            AlarmKind::TempLow => "温度过低",
+            AlarmKind::HumHigh => "湿度过高",
+            AlarmKind::HumLow => "湿度过低",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 报警状态变化
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlarmEvent {
+    /// 读数越过阈值，报警开始
+    Raised { kind: AlarmKind, value: f32 },
+    /// 读数回到阈值内侧超过回差，报警解除
+    Cleared { kind: AlarmKind, value: f32 },
+}
+
+impl fmt::Display for AlarmEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlarmEvent::Raised { kind, value } => write!(f, "报警: {kind} ({value:.1})"),
+            AlarmEvent::Cleared { kind, value } => write!(f, "报警解除: {kind} ({value:.1})"),
+        }
+    }
+}
+
+/// 带回差的阈值报警状态机
+pub struct AlarmMonitor {
+    config: AlarmConfig,
+    active: [bool; 4],
+}
+
+impl AlarmMonitor {
+    pub fn new(config: AlarmConfig) -> Self {
+        Self {
+            config,
+            active: [false; 4],
+        }
+    }
+
+    /// 检查一次读数，返回本次发生的报警开始/解除事件
+    ///
+    /// 报警持续期间不会重复产生 `Raised` 事件
+    pub fn evaluate(&mut self, slot: &InfoSlot) -> Vec<AlarmEvent> {
+        let mut events = Vec::new();
+        for kind in AlarmKind::ALL {
+            let Some(threshold) = kind.threshold(&self.config) else {
+                continue;
+            };
+            let value = kind.value(slot);
+            let active = &mut self.active[kind.index()];
+            let margin = self.config.clear_margin;
+
+            let (breached, cleared) = if kind.is_high() {
+                (value > threshold, value < threshold - margin)
+            } else {
+                (value < threshold, value > threshold + margin)
+            };
+
+            if !*active && breached {
+                *active = true;
+                events.push(AlarmEvent::Raised { kind, value });
+            } else if *active && cleared {
+                *active = false;
+                events.push(AlarmEvent::Cleared { kind, value });
+            }
+        }
+        events
+    }
+
+    /// 当前处于报警状态的类型
+    pub fn active(&self) -> impl Iterator<Item = AlarmKind> + '_ {
+        AlarmKind::ALL
+            .into_iter()
+            .filter(|kind| self.active[kind.index()])
+    }
+
+    /// 是否有任意报警处于激活状态
+    pub fn is_alarming(&self) -> bool {
+        self.active.iter().any(|&active| active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_only(high: f32, margin: f32) -> AlarmConfig {
+        AlarmConfig {
+            temp_high: Some(high),
+            temp_low: None,
+            hum_high: None,
+            hum_low: None,
+            clear_margin: margin,
+        }
+    }
+
+    #[test]
+    fn test_raise_and_clear_with_hysteresis() {
+        let mut monitor = AlarmMonitor::new(temp_only(30.0, 1.0));
+        let slot = |t| InfoSlot::new_from_f32(t, 50.0);
+
+        assert!(monitor.evaluate(&slot(29.5)).is_empty());
+        assert_eq!(
+            monitor.evaluate(&slot(30.5)),
+            vec![AlarmEvent::Raised { kind: AlarmKind::TempHigh, value: 30.5 }]
+        );
+        assert!(monitor.is_alarming());
+
+        // 在阈值附近抖动不会重复触发，也不会解除
+        assert!(monitor.evaluate(&slot(29.5)).is_empty());
+        assert!(monitor.evaluate(&slot(30.5)).is_empty());
+        assert!(monitor.evaluate(&slot(29.0)).is_empty());
+
+        assert_eq!(
+            monitor.evaluate(&slot(28.5)),
+            vec![AlarmEvent::Cleared { kind: AlarmKind::TempHigh, value: 28.5 }]
+        );
+        assert!(!monitor.is_alarming());
+    }
+
+    #[test]
+    fn test_without_humidity() {
+        let mut monitor = AlarmMonitor::new(AlarmConfig::default().without_humidity());
+        // 不测湿度的传感器湿度固定为 0%，不应触发湿度过低报警
+        assert!(monitor.evaluate(&InfoSlot::new_from_f32(25.0, 0.0)).is_empty());
+        assert!(!monitor.is_alarming());
+    }
+
+    #[test]
+    fn test_low_thresholds_and_multiple_alarms() {
+        let mut monitor = AlarmMonitor::new(AlarmConfig::default());
+
+        let events = monitor.evaluate(&InfoSlot::new_from_f32(4.0, 90.0));
+        assert_eq!(
+            events,
+            vec![
+                AlarmEvent::Raised { kind: AlarmKind::TempLow, value: 4.0 },
+                AlarmEvent::Raised { kind: AlarmKind::HumHigh, value: 90.0 },
+            ]
+        );
+        assert_eq!(
+            monitor.active().collect::<Vec<_>>(),
+            vec![AlarmKind::TempLow, AlarmKind::HumHigh]
+        );
+
+        // 温度回到下限以上但未超过回差，仍保持报警；湿度恢复
+        let events = monitor.evaluate(&InfoSlot::new_from_f32(5.5, 60.0));
+        assert_eq!(
+            events,
+            vec![AlarmEvent::Cleared { kind: AlarmKind::HumHigh, value: 60.0 }]
+        );
+        assert_eq!(monitor.active().collect::<Vec<_>>(), vec![AlarmKind::TempLow]);
+    }
+}
@@ -0,0 +1,315 @@
+//! HTTP 数据查询服务
+//!
+//! 通过 `HttpServerBuilder` 启动 `EspHttpServer`，默认监听 80 端口，提供:
+//! - `GET /`: 主循环最近发布的状态快照，格式同 `Snapshot::to_json`，
+//!   需要通过 `HttpServerBuilder::snapshot` 设置，主循环尚未发布时返回 503
+//! - `GET /latest`: 最新一条读数，如 `{"time":1700000000,"temperature":25.3,"humidity":60.0}`，
+//!   数据库为空时返回 404
+//! - `GET /history?from=..&to=..`: 时间范围 `[from, to]` 内的读数数组，
+//!   `from`/`to` 的单位与存储时间戳相同（见 `TimeDB::time_unit`），格式同 `TimeDB::export_json`。
+//!   范围内超过 `MAX_HISTORY_RECORDS` 条记录时返回 413，需要缩小查询范围
+//! - `GET /api/storage`: 存储健康状态汇总，包括记录数、用量、首尾记录时间、写入速率、
+//!   预计达到容量警戒线的时间、写放大系数和最近一轮数据校验结果，如
+//!   `{"records":512,"used_bytes":4096,"capacity_bytes":24576,"pct":16.7,"oldest_time":1700000000,
+//!   "newest_time":1700030660,"insert_rate_per_hour":60.0,"time_to_full_secs":86400,
+//!   "write_amplification":2.50,"last_scrub":{"valid":512,"corrupt":0},"corrupt_records":0}`。
+//!   数据库为空时首尾时间为 `null`，写入少于两条时速率和时间为 `null`，
+//!   尚未完成一轮校验时 `last_scrub` 和 `corrupt_records` 为 `null`。
+//!   只使用缓存值，不遍历整个数据库
+//!
+//! ## 注意事项
+//! - ESP-IDF 的 httpd 运行在独立的 FreeRTOS 任务中，处理函数会阻塞该任务直到响应发送完毕，
+//!   同一时间只处理一个请求
+//! - 处理函数需要获取数据库锁，查询大范围历史数据时主循环的写入也会等待
+//! - 返回的 `HttpServer` 被丢弃时服务停止，需要在整个程序运行期间保持存活
+
+use crate::data::info_def::InfoSlot;
+use crate::data::snapshot::Snapshot;
+use crate::data::time_db::{StorageReport, TimeDB};
+use anyhow::Result;
+use esp_idf_svc::http::server::{Configuration, EspHttpConnection, EspHttpServer, Request};
+use esp_idf_svc::http::Method;
+use esp_idf_svc::io::Write;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// 默认监听端口
+pub const DEFAULT_PORT: u16 = 80;
+
+/// `/history` 单次返回的最大记录数，每条约 55 字节，响应体约 55KB
+pub const MAX_HISTORY_RECORDS: usize = 1000;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RangeQueryError {
+    #[error("缺少查询参数 {0}")]
+    Missing(&'static str),
+    #[error("查询参数 {0} 不是有效的 unix 时间戳")]
+    Invalid(&'static str),
+    #[error("查询参数 from 不能大于 to")]
+    Reversed,
+}
+
+/// HTTP 服务配置
+pub struct HttpServerBuilder {
+    port: u16,
+    stack_size: Option<usize>,
+    snapshot: Option<Arc<Mutex<Option<Snapshot>>>>,
+}
+
+impl Default for HttpServerBuilder {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            stack_size: None,
+            snapshot: None,
+        }
+    }
+}
+
+impl HttpServerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置监听端口，默认 80
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// 设置 httpd 任务的栈大小（字节），默认使用 ESP-IDF 的默认值
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    /// 设置主循环发布状态快照的位置，设置后注册 `GET /` 返回最新快照
+    pub fn snapshot(mut self, snapshot: Arc<Mutex<Option<Snapshot>>>) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// 启动 HTTP 服务并注册 `/latest`、`/history` 和 `/api/storage` 处理函数，
+    /// 设置了快照时还会注册 `/`
+    ///
+    /// 需要在 WiFi 连接后调用
+    pub fn start(self, db: Arc<Mutex<TimeDB>>) -> Result<HttpServer> {
+        let default_conf = Configuration::default();
+        let conf = Configuration {
+            http_port: self.port,
+            stack_size: self.stack_size.unwrap_or(default_conf.stack_size),
+            ..default_conf
+        };
+        let mut server = EspHttpServer::new(&conf)?;
+
+        if let Some(snapshot) = self.snapshot {
+            server.fn_handler("/", Method::Get, move |req| {
+                let body = snapshot.lock().unwrap().as_ref().map(Snapshot::to_json);
+                match body {
+                    Some(body) => send_json(req, 200, &body),
+                    None => send_json(req, 503, r#"{"error":"尚未采集到状态快照"}"#),
+                }
+            })?;
+        }
+
+        let latest_db = db.clone();
+        server.fn_handler("/latest", Method::Get, move |req| {
+            let latest = latest_db.lock().unwrap().latest_with_time();
+            match latest {
+                Some((time, slot)) => send_json(req, 200, &latest_json(time, &slot)),
+                None => send_json(req, 404, r#"{"error":"数据库中无数据"}"#),
+            }
+        })?;
+
+        let storage_db = db.clone();
+        server.fn_handler("/api/storage", Method::Get, move |req| {
+            // 持有数据库锁期间主循环无法写入，报告只使用缓存值
+            let body = storage_json(&storage_db.lock().unwrap().storage_report());
+            send_json(req, 200, &body)
+        })?;
+
+        server.fn_handler("/history", Method::Get, move |req| {
+            let (from, to) = match parse_time_range(req.uri()) {
+                Ok(range) => range,
+                Err(e) => return send_json(req, 400, &format!(r#"{{"error":"{e}"}}"#)),
+            };
+            let (body, truncated) = db
+                .lock()
+                .unwrap()
+                .export_json_bounded(from, to, MAX_HISTORY_RECORDS);
+            if truncated {
+                return send_json(req, 413, &history_too_large_json());
+            }
+            send_json(req, 200, &body)
+        })?;
+
+        log::info!("HTTP 服务已启动，端口: {}", self.port);
+        Ok(HttpServer {
+            _server: server,
+            port: self.port,
+        })
+    }
+}
+
+/// 运行中的 HTTP 服务，被丢弃时服务停止
+pub struct HttpServer {
+    _server: EspHttpServer<'static>,
+    port: u16,
+}
+
+impl HttpServer {
+    /// 监听端口
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+/// `/history` 结果超过上限时的错误响应
+fn history_too_large_json() -> String {
+    format!(r#"{{"error":"时间范围内的记录超过 {MAX_HISTORY_RECORDS} 条，请缩小查询范围"}}"#)
+}
+
+fn send_json(req: Request<&mut EspHttpConnection<'_>>, status: u16, body: &str) -> Result<()> {
+    let mut resp = req.into_response(status, None, &[("Content-Type", "application/json")])?;
+    resp.write_all(body.as_bytes())?;
+    Ok(())
+}
+
+/// 将最新读数格式化为 `/latest` 的响应体，温湿度按记录的精度输出
+fn latest_json(time: i64, slot: &InfoSlot) -> String {
+    let mut json = format!(r#"{{"time":{time},"#);
+    slot.write_json_fields(&mut json);
+    json.push('}');
+    json
+}
+
+/// 将存储健康状态格式化为 `/api/storage` 的响应体
+fn storage_json(report: &StorageReport) -> String {
+    let null = || "null".to_string();
+    format!(
+        concat!(
+            r#"{{"records":{},"used_bytes":{},"capacity_bytes":{},"pct":{:.1},"#,
+            r#""oldest_time":{},"newest_time":{},"insert_rate_per_hour":{},"time_to_full_secs":{},"#,
+            r#""write_amplification":{:.2},"last_scrub":{},"corrupt_records":{}}}"#
+        ),
+        report.records,
+        report.usage.used_bytes,
+        report.usage.capacity_bytes,
+        report.usage.pct,
+        report.oldest_time.map_or_else(null, |time| time.to_string()),
+        report.newest_time.map_or_else(null, |time| time.to_string()),
+        report.insert_rate_per_hour.map_or_else(null, |rate| format!("{rate:.1}")),
+        report.time_to_full.map_or_else(null, |d| d.as_secs().to_string()),
+        report.write_amplification,
+        report.last_scrub.map_or_else(null, |scrub| {
+            format!(r#"{{"valid":{},"corrupt":{}}}"#, scrub.valid, scrub.corrupt)
+        }),
+        report.last_scrub.map_or_else(null, |scrub| scrub.corrupt.to_string())
+    )
+}
+
+/// 从请求 URI 的查询字符串中解析 `from` 和 `to`
+fn parse_time_range(uri: &str) -> Result<(i64, i64), RangeQueryError> {
+    let query = uri.split_once('?').map_or("", |(_, query)| query);
+    let param = |name: &'static str| {
+        let value = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+            .ok_or(RangeQueryError::Missing(name))?;
+        value.parse::<i64>().map_err(|_| RangeQueryError::Invalid(name))
+    };
+
+    let (from, to) = (param("from")?, param("to")?);
+    if from > to {
+        return Err(RangeQueryError::Reversed);
+    }
+    Ok((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::time_db::{ScrubReport, Usage};
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_time_range() {
+        assert_eq!(
+            parse_time_range("/history?from=1700000000&to=1700003600"),
+            Ok((1700000000, 1700003600))
+        );
+        assert_eq!(parse_time_range("/history?to=20&x=1&from=10"), Ok((10, 20)));
+        assert_eq!(parse_time_range("/history"), Err(RangeQueryError::Missing("from")));
+        assert_eq!(parse_time_range("/history?from=10"), Err(RangeQueryError::Missing("to")));
+        assert_eq!(
+            parse_time_range("/history?from=abc&to=20"),
+            Err(RangeQueryError::Invalid("from"))
+        );
+        assert_eq!(parse_time_range("/history?from=30&to=20"), Err(RangeQueryError::Reversed));
+    }
+
+    #[test]
+    fn test_storage_json_populated() {
+        let report = StorageReport {
+            records: 512,
+            usage: Usage {
+                used_bytes: 4096,
+                capacity_bytes: 16384,
+                pct: 25.0,
+            },
+            oldest_time: Some(1_700_000_000),
+            newest_time: Some(1_700_030_660),
+            insert_rate_per_hour: Some(720.0),
+            time_to_full: Some(Duration::from_secs(86400)),
+            write_amplification: 2.5,
+            last_scrub: Some(ScrubReport { valid: 510, corrupt: 2 }),
+        };
+        assert_eq!(
+            storage_json(&report),
+            concat!(
+                r#"{"records":512,"used_bytes":4096,"capacity_bytes":16384,"pct":25.0,"#,
+                r#""oldest_time":1700000000,"newest_time":1700030660,"insert_rate_per_hour":720.0,"#,
+                r#""time_to_full_secs":86400,"write_amplification":2.50,"#,
+                r#""last_scrub":{"valid":510,"corrupt":2},"corrupt_records":2}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_storage_json_empty() {
+        let report = StorageReport {
+            records: 0,
+            usage: Usage {
+                used_bytes: 0,
+                capacity_bytes: 16384,
+                pct: 0.0,
+            },
+            oldest_time: None,
+            newest_time: None,
+            insert_rate_per_hour: None,
+            time_to_full: None,
+            write_amplification: 0.0,
+            last_scrub: None,
+        };
+        assert_eq!(
+            storage_json(&report),
+            concat!(
+                r#"{"records":0,"used_bytes":0,"capacity_bytes":16384,"pct":0.0,"#,
+                r#""oldest_time":null,"newest_time":null,"insert_rate_per_hour":null,"#,
+                r#""time_to_full_secs":null,"write_amplification":0.00,"#,
+                r#""last_scrub":null,"corrupt_records":null}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_latest_json() {
+        let slot = InfoSlot::new_from_f32(25.3, 60.0);
+        assert_eq!(
+            latest_json(1700000000, &slot),
+            r#"{"time":1700000000,"temperature":25.3,"humidity":60.0}"#
+        );
+    }
+}
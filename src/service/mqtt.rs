@@ -0,0 +1,391 @@
+//! MQTT 读数发布与远程命令
+//!
+//! 每次读取后调用 `MqttPublisher::publish`，将读数以 JSON 发布到指定主题，如
+//! `{"time":1700000000,"temperature":25.3,"humidity":60.0}`。
+//!
+//! ## 远程命令
+//! 通过 `MqttPublisher::with_commands` 创建时订阅命令主题，负载为 JSON 对象：
+//! - `{"cmd":"read"}`: 立即读取一次传感器
+//! - `{"cmd":"reboot"}`: 在当前读数写入数据库后重启
+//! - `{"cmd":"set_interval","secs":10}`: 修改采样间隔，范围见 `MIN_SAMPLE_INTERVAL`/`MAX_SAMPLE_INTERVAL`
+//!
+//! 主循环调用 `next_command` 取出命令并执行，执行后调用 `ack` 回复。结果发布到
+//! `<命令主题>/result`，如 `{"cmd":"read","ok":true}`；无法解析或未知的命令不会交给主循环，
+//! 直接回复 `{"ok":false,"error":"未知命令: foo"}`。
+//!
+//! ## 注意事项
+//! - 连接断开后由 ESP-IDF 的 MQTT 任务按 `reconnect_timeout` 自动重连，`publish` 不会阻塞主循环
+//! - 离线期间的读数缓存在长度为 `OFFLINE_QUEUE_LEN` 的循环队列中，队列满时丢弃最旧的读数，
+//!   重连后的第一次 `publish` 会按时间顺序补发
+//! - 消息使用 QoS 1 放入 MQTT 客户端的发送队列，由 MQTT 任务在后台发送
+
+use crate::data::info_def::InfoSlot;
+use crate::utils::circular_queue::CircularQueue;
+use crate::utils::text::truncate_chars;
+use anyhow::Result;
+use esp_idf_svc::mqtt::client::{Details, EspMqttClient, EventPayload, MqttClientConfiguration, QoS};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// 离线时最多缓存的读数条数
+pub const OFFLINE_QUEUE_LEN: usize = 16;
+
+/// 断线后的重连间隔
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `set_interval` 允许的最小采样间隔，DHT22 要求两次读取至少间隔 2 秒
+pub const MIN_SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `set_interval` 允许的最大采样间隔
+pub const MAX_SAMPLE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 未知命令名称在回复中保留的最大字符数
+const MAX_COMMAND_NAME_CHARS: usize = 32;
+
+/// 远程命令
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// 立即读取一次传感器
+    Read,
+    /// 重启设备
+    Reboot,
+    /// 修改采样间隔
+    SetInterval(Duration),
+}
+
+impl Command {
+    /// 命令负载中 `cmd` 字段的值
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::Read => "read",
+            Command::Reboot => "reboot",
+            Command::SetInterval(_) => "set_interval",
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CommandError {
+    #[error("命令不是有效的 JSON 对象或缺少 cmd 字段")]
+    Malformed,
+    #[error("未知命令: {0}")]
+    Unknown(String),
+    #[error("采样间隔 secs 必须是 2~3600 之间的整数")]
+    InvalidInterval,
+}
+
+/// 命令订阅，只在 `with_commands` 创建时存在
+struct CommandChannel {
+    topic: String,
+    response_topic: String,
+    received: Receiver<Result<Command, CommandError>>,
+    /// 连接（重新）建立后需要订阅命令主题
+    subscribe_pending: Arc<AtomicBool>,
+}
+
+pub struct MqttPublisher {
+    client: EspMqttClient<'static>,
+    topic: String,
+    connected: Arc<AtomicBool>,
+    pending: CircularQueue<String, OFFLINE_QUEUE_LEN>,
+    commands: Option<CommandChannel>,
+}
+
+impl MqttPublisher {
+    /// 创建 MQTT 客户端并在后台开始连接，不等待连接完成
+    ///
+    /// # 参数
+    /// * `broker_url` - 如 `mqtt://192.168.1.10:1883`
+    /// * `topic` - 发布读数的主题
+    pub fn new(broker_url: &str, topic: &str) -> Result<Self> {
+        Self::connect(broker_url, topic, None)
+    }
+
+    /// 同 `new`，并在连接后订阅 `command_topic` 接收远程命令，格式见模块文档
+    pub fn with_commands(broker_url: &str, topic: &str, command_topic: &str) -> Result<Self> {
+        Self::connect(broker_url, topic, Some(command_topic))
+    }
+
+    fn connect(broker_url: &str, topic: &str, command_topic: Option<&str>) -> Result<Self> {
+        let connected = Arc::new(AtomicBool::new(false));
+        let cb_connected = connected.clone();
+        let subscribe_pending = Arc::new(AtomicBool::new(false));
+        let cb_subscribe_pending = subscribe_pending.clone();
+        let (command_tx, command_rx) = mpsc::channel();
+        let cb_command_topic = command_topic.map(str::to_string);
+
+        let conf = MqttClientConfiguration {
+            reconnect_timeout: Some(RECONNECT_TIMEOUT),
+            ..Default::default()
+        };
+        let client = EspMqttClient::new_cb(broker_url, &conf, move |event| match event.payload() {
+            EventPayload::Connected(_) => {
+                log::info!("MQTT 已连接");
+                cb_connected.store(true, Ordering::Relaxed);
+                cb_subscribe_pending.store(true, Ordering::Relaxed);
+            }
+            EventPayload::Received {
+                topic,
+                data,
+                details: Details::Complete,
+                ..
+            } => {
+                let command = cb_command_topic
+                    .as_deref()
+                    .and_then(|command_topic| route_message(command_topic, topic, data));
+                if let Some(command) = command {
+                    // 主循环已退出时接收端被丢弃，命令无需处理
+                    let _ = command_tx.send(command);
+                }
+            }
+            EventPayload::Disconnected => {
+                log::warn!("MQTT 连接断开，{}s 后重连", RECONNECT_TIMEOUT.as_secs());
+                cb_connected.store(false, Ordering::Relaxed);
+            }
+            EventPayload::Error(e) => log::warn!("MQTT 错误: {e:?}"),
+            _ => {}
+        })?;
+
+        log::info!("MQTT 客户端已创建: {broker_url}, 主题: {topic}");
+        Ok(Self {
+            client,
+            topic: topic.to_string(),
+            connected,
+            pending: CircularQueue::new(),
+            commands: command_topic.map(|command_topic| CommandChannel {
+                topic: command_topic.to_string(),
+                response_topic: format!("{command_topic}/result"),
+                received: command_rx,
+                subscribe_pending,
+            }),
+        })
+    }
+
+    /// 取出下一条远程命令，没有命令时返回 `None`
+    ///
+    /// 连接（重新）建立后先订阅命令主题，需要在主循环中定期调用。
+    /// 无法解析或未知的命令直接回复错误，不会返回给调用方
+    pub fn next_command(&mut self) -> Option<Command> {
+        let channel = self.commands.as_ref()?;
+        if self.connected.load(Ordering::Relaxed)
+            && channel.subscribe_pending.swap(false, Ordering::Relaxed)
+        {
+            match self.client.subscribe(&channel.topic, QoS::AtLeastOnce) {
+                Ok(_) => log::info!("已订阅 MQTT 命令主题: {}", channel.topic),
+                Err(e) => {
+                    log::warn!("订阅 MQTT 命令主题失败: {e:?}");
+                    channel.subscribe_pending.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+        loop {
+            // 回复错误需要可变借用 self，每次重新获取接收端
+            let received = self.commands.as_ref()?.received.try_recv().ok()?;
+            match received {
+                Ok(command) => return Some(command),
+                Err(e) => {
+                    log::warn!("拒绝 MQTT 命令: {e}");
+                    self.respond(&error_json(&e));
+                }
+            }
+        }
+    }
+
+    /// 回复命令已执行
+    pub fn ack(&mut self, command: Command) {
+        self.respond(&ack_json(command));
+    }
+
+    fn respond(&mut self, payload: &str) {
+        let Some(channel) = self.commands.as_ref() else {
+            return;
+        };
+        let res = self.client.enqueue(
+            &channel.response_topic,
+            QoS::AtLeastOnce,
+            false,
+            payload.as_bytes(),
+        );
+        if let Err(e) = res {
+            log::warn!("MQTT 命令回复发送失败: {e:?}");
+        }
+    }
+
+    /// 发布一条读数，离线时缓存到队列中
+    ///
+    /// `timestamp` 为读数的 unix 时间戳，时间未同步时传入 `None`，JSON 中省略该字段
+    pub fn publish(&mut self, timestamp: Option<i64>, slot: &InfoSlot) {
+        if self.pending.push_overwrite(reading_json(timestamp, slot)).is_some() {
+            log::warn!("MQTT 离线队列已满，丢弃最旧的读数");
+        }
+        if !self.is_connected() {
+            log::info!("MQTT 未连接，已缓存 {} 条读数", self.pending.len());
+            return;
+        }
+        self.flush();
+    }
+
+    /// 是否已连接到 broker
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// 尚未发送的读数条数
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// 按时间顺序发送缓存的读数，发送失败时保留剩余读数等待下次发布
+    fn flush(&mut self) {
+        while let Some(payload) = self.pending.peek() {
+            let res = self
+                .client
+                .enqueue(&self.topic, QoS::AtLeastOnce, false, payload.as_bytes());
+            if let Err(e) = res {
+                log::warn!(
+                    "MQTT 读数入队失败: {e:?}，{} 条读数等待下次发布",
+                    self.pending.len()
+                );
+                break;
+            }
+            self.pending.pop();
+        }
+    }
+}
+
+/// 收到的消息属于 `command_topic` 时解析为命令，其他主题的消息返回 `None`
+fn route_message(
+    command_topic: &str,
+    topic: Option<&str>,
+    data: &[u8],
+) -> Option<Result<Command, CommandError>> {
+    (topic == Some(command_topic)).then(|| parse_command(data))
+}
+
+/// 解析命令负载，格式见模块文档
+pub fn parse_command(payload: &[u8]) -> Result<Command, CommandError> {
+    let json = std::str::from_utf8(payload).map_err(|_| CommandError::Malformed)?;
+    match json_field(json, "cmd").ok_or(CommandError::Malformed)? {
+        "read" => Ok(Command::Read),
+        "reboot" => Ok(Command::Reboot),
+        "set_interval" => json_field(json, "secs")
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .filter(|interval| (MIN_SAMPLE_INTERVAL..=MAX_SAMPLE_INTERVAL).contains(interval))
+            .map(Command::SetInterval)
+            .ok_or(CommandError::InvalidInterval),
+        // 名称会原样写回回复的 JSON，只接受不需要转义的字符
+        name if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => Err(
+            CommandError::Unknown(truncate_chars(name, MAX_COMMAND_NAME_CHARS).to_string()),
+        ),
+        _ => Err(CommandError::Malformed),
+    }
+}
+
+/// 从不含嵌套和转义的扁平 JSON 对象中取出 `key` 的值，字符串去掉引号，数字原样返回
+fn json_field<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+    let body = json.trim().strip_prefix('{')?.strip_suffix('}')?;
+    body.split(',').find_map(|pair| {
+        let (name, value) = pair.split_once(':')?;
+        if name.trim().strip_prefix('"')?.strip_suffix('"')? != key {
+            return None;
+        }
+        let value = value.trim();
+        Some(value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value))
+    })
+}
+
+fn ack_json(command: Command) -> String {
+    format!(r#"{{"cmd":"{}","ok":true}}"#, command.name())
+}
+
+fn error_json(error: &CommandError) -> String {
+    format!(r#"{{"ok":false,"error":"{error}"}}"#)
+}
+
+/// 将读数格式化为 JSON，温湿度按记录的精度输出
+fn reading_json(time: Option<i64>, slot: &InfoSlot) -> String {
+    let mut json = String::from("{");
+    if let Some(time) = time {
+        json.push_str(&format!(r#""time":{time},"#));
+    }
+    slot.write_json_fields(&mut json);
+    json.push('}');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_json() {
+        let slot = InfoSlot::new_from_f32(25.3, 60.0);
+        assert_eq!(
+            reading_json(Some(1700000000), &slot),
+            r#"{"time":1700000000,"temperature":25.3,"humidity":60.0}"#
+        );
+        assert_eq!(reading_json(None, &slot), r#"{"temperature":25.3,"humidity":60.0}"#);
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command(br#"{"cmd":"read"}"#), Ok(Command::Read));
+        assert_eq!(parse_command(br#" { "cmd" : "reboot" } "#), Ok(Command::Reboot));
+        assert_eq!(
+            parse_command(br#"{"secs":10,"cmd":"set_interval"}"#),
+            Ok(Command::SetInterval(Duration::from_secs(10)))
+        );
+
+        // 采样间隔缺失、不是整数或超出范围
+        for payload in [
+            r#"{"cmd":"set_interval"}"#,
+            r#"{"cmd":"set_interval","secs":"abc"}"#,
+            r#"{"cmd":"set_interval","secs":1}"#,
+            r#"{"cmd":"set_interval","secs":3601}"#,
+        ] {
+            assert_eq!(parse_command(payload.as_bytes()), Err(CommandError::InvalidInterval));
+        }
+
+        assert_eq!(
+            parse_command(br#"{"cmd":"format_flash"}"#),
+            Err(CommandError::Unknown("format_flash".to_string()))
+        );
+        assert_eq!(parse_command(br#"{"cmd":"a\"b"}"#), Err(CommandError::Malformed));
+        assert_eq!(parse_command(b"read"), Err(CommandError::Malformed));
+        assert_eq!(parse_command(br#"{"command":"read"}"#), Err(CommandError::Malformed));
+        assert_eq!(parse_command(&[0xFF, 0xFE]), Err(CommandError::Malformed));
+    }
+
+    #[test]
+    fn test_route_message() {
+        let topic = "esp-temperature-monitor/cmd";
+        assert_eq!(
+            route_message(topic, Some(topic), br#"{"cmd":"read"}"#),
+            Some(Ok(Command::Read))
+        );
+        assert_eq!(
+            route_message(topic, Some(topic), br#"{"cmd":"sleep"}"#),
+            Some(Err(CommandError::Unknown("sleep".to_string())))
+        );
+        // 其他主题或没有主题的消息不当作命令
+        assert_eq!(route_message(topic, Some("other/cmd"), br#"{"cmd":"read"}"#), None);
+        assert_eq!(route_message(topic, None, br#"{"cmd":"read"}"#), None);
+    }
+
+    #[test]
+    fn test_response_json() {
+        assert_eq!(ack_json(Command::Read), r#"{"cmd":"read","ok":true}"#);
+        assert_eq!(
+            ack_json(Command::SetInterval(Duration::from_secs(10))),
+            r#"{"cmd":"set_interval","ok":true}"#
+        );
+        assert_eq!(
+            error_json(&CommandError::Unknown("sleep".to_string())),
+            r#"{"ok":false,"error":"未知命令: sleep"}"#
+        );
+    }
+}
@@ -1,7 +1,10 @@
-use anyhow::Result;
-use esp_idf_svc::sntp::{EspSntp, SntpConf, SyncStatus};
+use crate::utils::watchdog;
+use anyhow::{bail, Result};
+use esp_idf_svc::sntp::{EspSntp, SntpConf, SyncMode, SyncStatus};
 use log::{info, warn};
-use std::time::Duration;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// NTP 时间同步配置
 pub struct NtpConfig {
@@ -11,6 +14,10 @@ pub struct NtpConfig {
     pub timeout_secs: u64,
     /// 是否等待同步完成
     pub wait_for_sync: bool,
+    /// 定期重新同步的间隔，`None` 表示仅依赖 SNTP 自身的轮询
+    pub resync_interval: Option<Duration>,
+    /// 是否使用平滑同步（通过 adjtime 逐渐校正时钟，而不是直接跳变）
+    pub smooth_sync: bool,
 }
 
 impl Default for NtpConfig {
@@ -24,6 +31,8 @@ impl Default for NtpConfig {
             ],
             timeout_secs: 30,
             wait_for_sync: true,
+            resync_interval: None,
+            smooth_sync: false,
         }
     }
 }
@@ -34,17 +43,30 @@ impl NtpConfig {
         Self::default()
     }
 
-    // /// 设置 NTP 服务器列表
-    // pub fn servers(mut self, servers: Vec<String>) -> Self {
-    //     self.servers = servers;
-    //     self
-    // }
+    /// 设置 NTP 服务器列表
+    ///
+    /// 会替换之前设置的服务器（包括 `china_servers()`/`global_servers()`），
+    /// 空的主机名会被忽略
+    pub fn servers(mut self, servers: Vec<String>) -> Self {
+        self.servers = servers
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| {
+                if s.is_empty() {
+                    warn!("忽略空的 NTP 服务器地址");
+                }
+                !s.is_empty()
+            })
+            .collect();
+        self
+    }
 
-    // /// 设置单个 NTP 服务器
-    // pub fn server(mut self, server: impl Into<String>) -> Self {
-    //     self.servers = vec![server.into()];
-    //     self
-    // }
+    /// 设置单个 NTP 服务器，例如局域网内的 NTP 服务
+    ///
+    /// 会替换之前设置的服务器，空的主机名会被忽略
+    pub fn server(self, server: impl Into<String>) -> Self {
+        self.servers(vec![server.into()])
+    }
 
     /// 设置中国常用的 NTP 服务器
     pub fn china_servers(mut self) -> Self {
@@ -57,16 +79,16 @@ impl NtpConfig {
         self
     }
 
-    // /// 使用全球通用的 NTP 服务器（更可靠）
-    // pub fn global_servers(mut self) -> Self {
-    //     self.servers = vec![
-    //         "pool.ntp.org".to_string(),
-    //         "time.google.com".to_string(),
-    //         "time.cloudflare.com".to_string(),
-    //         "time.apple.com".to_string(),
-    //     ];
-    //     self
-    // }
+    /// 使用全球通用的 NTP 服务器（更可靠）
+    pub fn global_servers(mut self) -> Self {
+        self.servers = vec![
+            "pool.ntp.org".to_string(),
+            "time.google.com".to_string(),
+            "time.cloudflare.com".to_string(),
+            "time.apple.com".to_string(),
+        ];
+        self
+    }
 
     /// 设置超时时间
     pub fn timeout(mut self, secs: u64) -> Self {
@@ -80,25 +102,88 @@ impl NtpConfig {
         self
     }
 
+    /// 设置定期重新同步的间隔
+    ///
+    /// 设备长时间运行时 RTC 会漂移，设置后会启动一个后台线程，
+    /// 距上次同步超过该间隔时强制 SNTP 重新同步
+    pub fn resync_interval(mut self, interval: Duration) -> Self {
+        self.resync_interval = Some(interval);
+        self
+    }
+
+    /// 设置是否使用平滑同步
+    ///
+    /// 平滑模式下时钟通过 adjtime 逐渐校正，偏差较大时 `SyncStatus::Completed`
+    /// 可能很久才出现；等待超时后若系统时间已经有效且偏差在收敛，视为同步基本完成
+    pub fn smooth_sync(mut self, smooth: bool) -> Self {
+        self.smooth_sync = smooth;
+        self
+    }
+
+    /// 选出实际交给 SNTP 的服务器列表
+    ///
+    /// SNTP 最多支持 `max` 个服务器（由 `CONFIG_LWIP_SNTP_MAX_SERVERS` 决定），
+    /// 超出部分会被丢弃并打印警告；列表为空时回退到 pool.ntp.org。
+    fn sntp_servers(&self, max: usize) -> Vec<&str> {
+        if self.servers.is_empty() {
+            warn!("未配置 NTP 服务器，使用 pool.ntp.org");
+            return vec!["pool.ntp.org"];
+        }
+
+        let used: Vec<&str> = self
+            .servers
+            .iter()
+            .take(max)
+            .map(|s| s.as_str())
+            .collect();
+        if self.servers.len() > max {
+            warn!(
+                "SNTP 最多支持 {} 个服务器，已忽略: {:?}",
+                max,
+                &self.servers[max..]
+            );
+        }
+        info!("使用 {} 个 NTP 服务器: {:?}", used.len(), used);
+        used
+    }
+
     /// 初始化并启动 NTP 时间同步
-    pub fn init(self) -> Result<EspSntp<'static>> {
+    ///
+    /// 返回的 `NtpHandle` 需要一直持有，drop 后 SNTP 服务和重新同步线程都会停止；
+    /// `NtpSyncOutcome` 表示等待阶段是否真正完成了同步，由调用方决定如何处理
+    pub fn init(self) -> Result<(NtpHandle, NtpSyncOutcome)> {
         info!("正在初始化 NTP 时间同步...");
         info!("NTP 服务器: {:?}", self.servers);
 
         // 创建 SNTP 配置
-        let sntp_conf = SntpConf {
-            servers: [
-                self.servers.first().map(|s| s.as_str()).unwrap_or("pool.ntp.org"),
-            ],
-            ..Default::default()
-        };
+        // 配置的服务器少于槽位数时，剩余槽位循环复用已配置的服务器
+        let mut sntp_conf = SntpConf::default();
+        let servers = self.sntp_servers(sntp_conf.servers.len());
+        for (slot, server) in sntp_conf.servers.iter_mut().zip(servers.iter().cycle()) {
+            *slot = *server;
+        }
+        if self.smooth_sync {
+            sntp_conf.sync_mode = SyncMode::Smooth;
+            info!("使用平滑同步模式");
+        }
 
-        // 初始化 SNTP
-        let sntp = EspSntp::new(&sntp_conf)?;
+        // 初始化 SNTP，每次同步完成时记录时间并计算本地时钟漂移
+        let state = Arc::new(Mutex::new(SyncState::default()));
+        let cb_state = state.clone();
+        let sntp = EspSntp::new_with_callback(&sntp_conf, move |synced| {
+            let now = Instant::now();
+            let mut state = cb_state.lock().unwrap();
+            if let (Some(at), Some(prev)) = (state.last_sync, state.last_synced_time) {
+                let drift = clock_drift_secs(prev, now - at, synced);
+                info!("NTP 重新同步完成，本地时钟漂移 {drift:+.3} 秒");
+            }
+            state.last_sync = Some(now);
+            state.last_synced_time = Some(synced);
+        })?;
         info!("NTP 客户端已启动");
 
         // 如果需要等待同步
-        if self.wait_for_sync {
+        let outcome = if self.wait_for_sync {
             info!("正在同步时间，请稍候...");
             
             // 给 SNTP 服务一些时间来启动
@@ -108,8 +193,11 @@ impl NtpConfig {
             let timeout = Duration::from_secs(self.timeout_secs);
             let mut last_status_print = std::time::Instant::now();
             let mut reset_count = 0;
+            // 平滑模式下上一次观察到的残余偏差，用于判断是否在收敛
+            let mut prev_residual: Option<Duration> = None;
 
             loop {
+                watchdog::feed();
                 let status = sntp.get_sync_status();
                 let elapsed = start.elapsed();
                 
@@ -117,7 +205,7 @@ impl NtpConfig {
                     SyncStatus::Completed => {
                         info!("✅ 时间同步完成！耗时 {:.1} 秒", elapsed.as_secs_f32());
                         print_current_time();
-                        break;
+                        break NtpSyncOutcome::Completed { elapsed };
                     }
                     SyncStatus::InProgress => {
                         // 每 5 秒打印一次进度
@@ -126,10 +214,23 @@ impl NtpConfig {
                             last_status_print = std::time::Instant::now();
                         }
                         
+                        let residual = if self.smooth_sync { smooth_residual() } else { None };
+
                         if elapsed > timeout {
+                            if let Some(residual) = residual {
+                                if smooth_sync_converging(unix_now(), prev_residual, residual) {
+                                    info!(
+                                        "✅ 平滑同步进行中，系统时间已有效，残余偏差 {:.3} 秒",
+                                        residual.as_secs_f32()
+                                    );
+                                    print_current_time();
+                                    break NtpSyncOutcome::Converging { residual };
+                                }
+                            }
                             warn!("⚠️  时间同步超时（{} 秒），将在后台继续同步", self.timeout_secs);
-                            break;
+                            break NtpSyncOutcome::TimedOut;
                         }
+                        prev_residual = residual.or(prev_residual);
                         std::thread::sleep(Duration::from_millis(500));
                     }
                     SyncStatus::Reset => {
@@ -149,7 +250,7 @@ impl NtpConfig {
                             warn!("  1. 检查网络连接是否正常");
                             warn!("  2. 尝试更换 NTP 服务器（使用 .china_servers() 或 .server()）");
                             warn!("  3. 检查防火墙是否阻止 UDP 123 端口");
-                            break;
+                            break NtpSyncOutcome::TimedOut;
                         }
                         std::thread::sleep(Duration::from_millis(500));
                     }
@@ -157,10 +258,160 @@ impl NtpConfig {
             }
         } else {
             info!("NTP 同步已启动（后台运行）");
+            NtpSyncOutcome::BackgroundOnly
+        };
+
+        let resync_stop = match self.resync_interval {
+            Some(interval) => {
+                info!("已启用定期重新同步，间隔 {} 秒", interval.as_secs());
+                Some(spawn_resync_thread(interval, state.clone())?)
+            }
+            None => None,
+        };
+
+        let handle = NtpHandle {
+            _sntp: sntp,
+            state,
+            _resync_stop: resync_stop,
+        };
+        Ok((handle, outcome))
+    }
+}
+
+/// `NtpConfig::init` 等待同步的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NtpSyncOutcome {
+    /// 在超时前完成同步
+    Completed { elapsed: Duration },
+    /// 平滑模式下等待超时，但系统时间已有效且仍在逐渐校正
+    Converging { residual: Duration },
+    /// 等待超时，SNTP 仍在后台继续尝试
+    TimedOut,
+    /// 未等待同步（`wait_for_sync(false)`）
+    BackgroundOnly,
+}
+
+#[derive(Debug, Default)]
+struct SyncState {
+    /// 最近一次同步完成的时刻
+    last_sync: Option<Instant>,
+    /// 最近一次同步得到的 unix 时间
+    last_synced_time: Option<Duration>,
+}
+
+/// NTP 服务句柄
+///
+/// 持有 SNTP 服务和（可选的）定期重新同步线程
+pub struct NtpHandle {
+    _sntp: EspSntp<'static>,
+    state: Arc<Mutex<SyncState>>,
+    // drop 时断开通道，通知重新同步线程退出
+    _resync_stop: Option<mpsc::Sender<()>>,
+}
+
+impl NtpHandle {
+    /// 距离上次同步完成经过的时间，尚未同步过时返回 `None`
+    pub fn last_sync_age(&self) -> Option<Duration> {
+        self.state.lock().unwrap().last_sync.map(|t| t.elapsed())
+    }
+
+    /// 是否至少完成过一次同步（包括等待超时后在后台完成的同步）
+    pub fn is_synced(&self) -> bool {
+        self.state.lock().unwrap().last_sync.is_some()
+    }
+
+    /// 立即强制重新同步
+    pub fn force_resync(&self) -> Result<()> {
+        if !request_resync() {
+            bail!("SNTP 重新同步请求失败");
         }
+        Ok(())
+    }
+}
+
+/// 系统时间早于该值（2023-11-14）时视为从未被设置过
+const MIN_PLAUSIBLE_UNIX_TIME: Duration = Duration::from_secs(1_700_000_000);
 
-        Ok(sntp)
+/// 当前系统时间（unix 时间），时钟早于 1970 年时返回 0
+fn unix_now() -> Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// 平滑模式下 adjtime 尚未校正完的偏差（绝对值），查询失败时返回 `None`
+fn smooth_residual() -> Option<Duration> {
+    let mut delta = esp_idf_svc::sys::timeval { tv_sec: 0, tv_usec: 0 };
+    let ret = unsafe { esp_idf_svc::sys::adjtime(std::ptr::null(), &mut delta) };
+    if ret != 0 {
+        return None;
     }
+    let micros = (delta.tv_sec * 1_000_000 + i64::from(delta.tv_usec)).unsigned_abs();
+    Some(Duration::from_micros(micros))
+}
+
+/// 平滑模式超时后判断同步是否“基本完成”
+///
+/// 系统时间已经是有效时间（说明至少收到过一次 NTP 响应），
+/// 且残余偏差没有比上一次观察时更大（时钟在向正确时间收敛）
+fn smooth_sync_converging(
+    unix_now: Duration,
+    prev_residual: Option<Duration>,
+    residual: Duration,
+) -> bool {
+    unix_now >= MIN_PLAUSIBLE_UNIX_TIME && prev_residual.is_none_or(|prev| residual <= prev)
+}
+
+/// 请求 SNTP 立即重新同步，成功返回 true
+fn request_resync() -> bool {
+    unsafe { esp_idf_svc::sys::esp_sntp_restart() }
+}
+
+/// 启动定期重新同步线程，返回用于停止线程的发送端
+fn spawn_resync_thread(
+    interval: Duration,
+    state: Arc<Mutex<SyncState>>,
+) -> Result<mpsc::Sender<()>> {
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    // 检查周期不超过 1 分钟，保证 drop 句柄后线程能及时退出
+    let check_period = interval.min(Duration::from_secs(60));
+
+    std::thread::Builder::new()
+        .name("ntp-resync".into())
+        .stack_size(4096)
+        .spawn(move || {
+            let mut last_request = Instant::now();
+            loop {
+                match stop_rx.recv_timeout(check_period) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    _ => break,
+                }
+
+                let last_sync = state.lock().unwrap().last_sync;
+                // 以最近一次同步或最近一次请求中较晚的时刻为基准，避免同步失败时反复请求
+                let reference = last_sync.map_or(last_request, |t| t.max(last_request));
+                if reference.elapsed() >= interval {
+                    info!("距上次同步已超过 {} 秒，请求重新同步", interval.as_secs());
+                    if !request_resync() {
+                        warn!("SNTP 重新同步请求失败");
+                    }
+                    last_request = Instant::now();
+                }
+            }
+            info!("NTP 重新同步线程已退出");
+        })?;
+
+    Ok(stop_tx)
+}
+
+/// 计算本地时钟相对 NTP 的漂移（秒），正值表示本地时钟偏慢
+///
+/// # 参数
+/// - `prev_synced`: 上次同步得到的 unix 时间
+/// - `elapsed`: 两次同步之间经过的单调时间
+/// - `synced`: 本次同步得到的 unix 时间
+fn clock_drift_secs(prev_synced: Duration, elapsed: Duration, synced: Duration) -> f64 {
+    synced.as_secs_f64() - (prev_synced + elapsed).as_secs_f64()
 }
 
 /// 测试网络连接（在同步 NTP 前调用）
@@ -177,6 +428,7 @@ pub fn test_network_connectivity() -> bool {
     ];
 
     for (ip, port, name) in direct_ips.iter() {
+        watchdog::feed();
         let addr = SocketAddr::new(*ip, *port);
         match TcpStream::connect_timeout(&addr, Duration::from_secs(3)) {
             Ok(_) => {
@@ -198,6 +450,7 @@ pub fn test_network_connectivity() -> bool {
     ];
 
     for (host, port) in test_targets.iter() {
+        watchdog::feed();
         info!("尝试解析并连接 {host}:{port}...");
         
         // 测试 DNS 解析
@@ -234,6 +487,40 @@ pub fn test_network_connectivity() -> bool {
     false
 }
 
+/// 重试网络连通性测试，失败后按指数退避等待
+///
+/// WiFi 刚连接时网络接口可能尚未就绪，单次测试容易误判。
+/// 任意一次测试成功立即返回 true，全部尝试失败返回 false。
+///
+/// # Arguments
+/// * `max_attempts` - 最大尝试次数（至少为 1）
+/// * `base_delay` - 第一次失败后的等待时间，之后每次翻倍，上限为 `MAX_BACKOFF`
+pub fn wait_for_network(max_attempts: u32, base_delay: Duration) -> bool {
+    let max_attempts = max_attempts.max(1);
+    for attempt in 1..=max_attempts {
+        watchdog::feed();
+        info!("网络连接测试 第 {attempt}/{max_attempts} 次");
+        if test_network_connectivity() {
+            return true;
+        }
+        if attempt < max_attempts {
+            let delay = backoff_delay(base_delay, attempt);
+            info!("{:.1} 秒后重试...", delay.as_secs_f32());
+            std::thread::sleep(delay);
+        }
+    }
+    false
+}
+
+/// 退避等待时间上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 第 `attempt` 次失败后的等待时间：`base * 2^(attempt - 1)`，不超过 `MAX_BACKOFF`
+fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+    base.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
 /// 打印当前系统时间
 pub fn print_current_time() {
     use time::{format_description, OffsetDateTime};
@@ -255,3 +542,78 @@ pub fn print_current_time() {
 // pub fn is_time_synced(sntp: &EspSntp) -> bool {
 //     matches!(sntp.get_sync_status(), SyncStatus::Completed)
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_builders_last_call_wins() {
+        let config = NtpConfig::new().china_servers().server("192.168.1.2");
+        assert_eq!(config.servers, vec!["192.168.1.2".to_string()]);
+
+        let config = NtpConfig::new()
+            .server("192.168.1.2")
+            .servers(vec!["ntp.lan".to_string(), " ".to_string(), " time.lan ".to_string()]);
+        assert_eq!(config.servers, vec!["ntp.lan".to_string(), "time.lan".to_string()]);
+
+        let config = NtpConfig::new().server("ntp.lan").global_servers();
+        assert_eq!(config.servers.len(), 4);
+        assert_eq!(config.servers[0], "pool.ntp.org");
+
+        // 空主机名被忽略，init 时会回退到 pool.ntp.org
+        let config = NtpConfig::new().server("");
+        assert!(config.servers.is_empty());
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let base = Duration::from_secs(1);
+        assert_eq!(backoff_delay(base, 1), Duration::from_secs(1));
+        assert_eq!(backoff_delay(base, 2), Duration::from_secs(2));
+        assert_eq!(backoff_delay(base, 4), Duration::from_secs(8));
+        // 超过上限后保持不变
+        assert_eq!(backoff_delay(base, 6), MAX_BACKOFF);
+        assert_eq!(backoff_delay(base, 100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_smooth_sync_converging() {
+        let now = Duration::from_secs(1_760_000_000);
+
+        // 残余偏差逐渐缩小：视为同步基本完成
+        let mut prev = None;
+        for residual_ms in [1800, 1200, 700, 300] {
+            let residual = Duration::from_millis(residual_ms);
+            assert!(smooth_sync_converging(now, prev, residual));
+            prev = Some(residual);
+        }
+
+        // 偏差变大说明没有在收敛
+        assert!(!smooth_sync_converging(
+            now,
+            Some(Duration::from_millis(300)),
+            Duration::from_millis(900)
+        ));
+
+        // 系统时间仍在 1970 年附近，说明从未收到 NTP 响应
+        assert!(!smooth_sync_converging(Duration::from_secs(42), None, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_clock_drift() {
+        let prev = Duration::from_secs(1_700_000_000);
+        let elapsed = Duration::from_secs(6 * 3600);
+
+        // 本地时钟与 NTP 一致
+        assert_eq!(clock_drift_secs(prev, elapsed, prev + elapsed), 0.0);
+
+        // 本地时钟慢了 1.5 秒
+        let synced = prev + elapsed + Duration::from_millis(1500);
+        assert!((clock_drift_secs(prev, elapsed, synced) - 1.5).abs() < 1e-6);
+
+        // 本地时钟快了 0.25 秒
+        let synced = prev + elapsed - Duration::from_millis(250);
+        assert!((clock_drift_secs(prev, elapsed, synced) + 0.25).abs() < 1e-6);
+    }
+}
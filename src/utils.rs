@@ -1,4 +1,8 @@
 pub mod circular_queue;
 pub mod rand;
 pub mod calculate;
-pub mod time;
\ No newline at end of file
+pub mod time;
+pub mod text;
+pub mod watchdog;
+#[cfg(feature = "fault-inject")]
+pub mod fault_inject;
\ No newline at end of file
@@ -1,13 +1,146 @@
+use super::circular_queue::CircularQueue;
+
+/// 向上对齐到 `align` 的整数倍，`align` 为 0 时原样返回
+///
+/// 结果超出 `usize` 范围时饱和为不超过 `usize::MAX` 的最大整数倍，需要检测溢出时请使用 `checked_align`
 pub fn quick_align(val: usize, align: usize) -> usize {
+    checked_align(val, align).unwrap_or_else(|| align_down(usize::MAX, align))
+}
+
+/// 向上对齐到 `align` 的整数倍，结果超出 `usize` 范围时返回 `None`，`align` 为 0 时原样返回
+pub fn checked_align(val: usize, align: usize) -> Option<usize> {
     if align == 0 {
-        return val;
+        return Some(val);
     }
     if (align & (align - 1)) == 0 {
         // align 是 2 的幂次方 (使用位运算，最快)
-        (val + align - 1) & !(align - 1)
+        val.checked_add(align - 1).map(|v| v & !(align - 1))
     } else {
         // align 不是 2 的幂次方 (使用通用模数运算)
+        val.div_ceil(align).checked_mul(align)
+    }
+}
+
+/// 向下对齐到 `align` 的整数倍，`align` 为 0 时原样返回
+pub fn align_down(val: usize, align: usize) -> usize {
+    if align == 0 {
+        return val;
+    }
+    if (align & (align - 1)) == 0 {
+        val & !(align - 1)
+    } else {
+        val / align * align
+    }
+}
+
+/// `val` 是否为 `align` 的整数倍，`align` 为 0 时视为已对齐
+pub fn is_aligned(val: usize, align: usize) -> bool {
+    align_down(val, align) == val
+}
+
+/// CRC-16/CCITT-FALSE（多项式 0x1021，初始值 0xFFFF）
+pub fn crc16_ccitt(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// 滑动平均窗口的最大长度，平滑窗口超过该值时按该值处理
+pub const MAX_SMOOTHING_WINDOW: usize = 16;
+
+/// 计算队列中最近 `window` 个读数的（温度, 湿度）平均值，不足 `window` 个时对已有读数求平均
+///
+/// `window` 为 0 按 1 处理；队列为空时返回 `None`
+pub fn average_recent<T, const N: usize>(
+    history: &CircularQueue<T, N>,
+    window: usize,
+) -> Option<(f32, f32)>
+where
+    T: Copy + Into<(f32, f32)>,
+{
+    let count = window.max(1).min(history.len());
+    if count == 0 {
+        return None;
+    }
+    let (temperature, humidity) = history
+        .iter()
+        .skip(history.len() - count)
+        .fold((0.0, 0.0), |(t, h), &item| {
+            let (rt, rh) = item.into();
+            (t + rt, h + rh)
+        });
+    Some((temperature / count as f32, humidity / count as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc16_ccitt() {
+        // 标准校验值
+        assert_eq!(crc16_ccitt(b"123456789"), 0x29B1);
+        assert_eq!(crc16_ccitt(&[]), 0xFFFF);
+    }
+
+    #[test]
+    fn test_align() {
+        assert_eq!(quick_align(5000, 4096), 8192);
+        assert_eq!(align_down(5000, 4096), 4096);
+        assert_eq!(align_down(8192, 4096), 8192);
+        assert!(is_aligned(8192, 4096));
+        assert!(!is_aligned(5000, 4096));
+
+        // 非 2 的幂次方
+        assert_eq!(quick_align(13, 6), 18);
+        assert_eq!(align_down(13, 6), 12);
+        assert_eq!(align_down(12, 6), 12);
+        assert!(is_aligned(12, 6));
+        assert!(!is_aligned(13, 6));
+
+        // align 为 0 时原样返回
+        assert_eq!(quick_align(13, 0), 13);
+        assert_eq!(align_down(13, 0), 13);
+        assert!(is_aligned(13, 0));
+    }
+
+    #[test]
+    fn test_align_overflow() {
+        assert_eq!(checked_align(usize::MAX - 1, 4096), None);
+        assert_eq!(checked_align(usize::MAX - 1, 6), None);
+        assert_eq!(checked_align(usize::MAX - 1, 2), Some(usize::MAX - 1));
+        assert_eq!(checked_align(usize::MAX - 1, 0), Some(usize::MAX - 1));
+
+        // 溢出时饱和，而不是回绕为很小的值
+        assert_eq!(quick_align(usize::MAX - 1, 4096), usize::MAX & !4095);
+        assert_eq!(quick_align(usize::MAX - 1, 6), usize::MAX / 6 * 6);
+    }
+
+    #[test]
+    fn test_average_recent() {
+        let mut history: CircularQueue<(f32, f32), 4> = CircularQueue::new();
+        assert_eq!(average_recent(&history, 3), None);
+
+        // 不足窗口长度时对已有读数求平均
+        history.push_overwrite((20.0, 40.0));
+        assert_eq!(average_recent(&history, 3), Some((20.0, 40.0)));
+        history.push_overwrite((21.0, 50.0));
+        assert_eq!(average_recent(&history, 3), Some((20.5, 45.0)));
+
+        // 只取最近 window 个读数
+        history.push_overwrite((22.0, 60.0));
+        history.push_overwrite((23.0, 70.0));
+        history.push_overwrite((24.0, 80.0));
+        assert_eq!(average_recent(&history, 3), Some((23.0, 70.0)));
 
-        val.div_ceil(align) * align
+        // 窗口为 0 按 1 处理，超过容量时按已有读数处理
+        assert_eq!(average_recent(&history, 0), Some((24.0, 80.0)));
+        assert_eq!(average_recent(&history, 100), Some((22.5, 65.0)));
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,6 @@
+use std::iter::{Chain, Take};
+use std::slice;
+
 /// 循环队列实现，使用 Vec 动态分配
 ///
 /// # 特性
@@ -10,7 +13,7 @@ pub struct CircularQueue<T, const N: usize> {
     buffer: Vec<Option<T>>, // 使用 Vec 存储元素
     capacity: usize,        // 队列容量
     head: usize,            // 队头位置（出队）
-    tail: usize,            // 队尾位置（入队）
+    len: usize,             // 当前元素数量
 }
 
 #[allow(unused)]
@@ -25,7 +28,7 @@ impl<T, const N: usize> CircularQueue<T, N> {
             buffer,
             capacity: N,
             head: 0,
-            tail: 0,
+            len: 0,
         }
     }
 
@@ -38,24 +41,25 @@ impl<T, const N: usize> CircularQueue<T, N> {
     /// 返回队列当前的元素数量
     #[inline]
     pub fn len(&self) -> usize {
-        if self.tail >= self.head {
-            self.tail - self.head
-        } else {
-            self.capacity - self.head + self.tail
-        }
+        self.len
     }
 
     /// 检查队列是否为空
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.head == self.tail && self.buffer[self.head].is_none()
+        self.len == 0
     }
 
     /// 检查队列是否已满
     #[inline]
     pub fn is_full(&self) -> bool {
-        let next_tail = (self.tail + 1) % self.capacity;
-        next_tail == self.head && self.buffer[self.head].is_some()
+        self.len == self.capacity
+    }
+
+    /// 队尾位置（下一个入队元素的位置）
+    #[inline]
+    fn tail(&self) -> usize {
+        (self.head + self.len) % self.capacity
     }
 
     /// 向队尾添加元素
@@ -68,8 +72,9 @@ impl<T, const N: usize> CircularQueue<T, N> {
             return Err(value);
         }
 
-        self.buffer[self.tail] = Some(value);
-        self.tail = (self.tail + 1) % self.capacity;
+        let tail = self.tail();
+        self.buffer[tail] = Some(value);
+        self.len += 1;
         Ok(())
     }
 
@@ -80,14 +85,14 @@ impl<T, const N: usize> CircularQueue<T, N> {
     /// - `Some(old_value)` - 队列已满，返回被覆盖的旧值
     pub fn push_overwrite(&mut self, value: T) -> Option<T> {
         if self.is_full() {
-            let old = self.buffer[self.head].take();
+            // 队列已满时队尾与队头重合，直接替换最旧的元素并前移队头
+            let old = self.buffer[self.head].replace(value);
             self.head = (self.head + 1) % self.capacity;
-            self.buffer[self.tail] = Some(value);
-            self.tail = (self.tail + 1) % self.capacity;
             old
         } else {
-            self.buffer[self.tail] = Some(value);
-            self.tail = (self.tail + 1) % self.capacity;
+            let tail = self.tail();
+            self.buffer[tail] = Some(value);
+            self.len += 1;
             None
         }
     }
@@ -104,6 +109,7 @@ impl<T, const N: usize> CircularQueue<T, N> {
 
         let value = self.buffer[self.head].take();
         self.head = (self.head + 1) % self.capacity;
+        self.len -= 1;
         value
     }
 
@@ -125,6 +131,35 @@ impl<T, const N: usize> CircularQueue<T, N> {
         }
     }
 
+    /// 查看队尾（最新入队的）元素但不移除
+    pub fn peek_back(&self) -> Option<&T> {
+        self.get_back(0)
+    }
+
+    /// 返回从队尾倒数的元素引用（0 表示最新入队的元素）
+    pub fn get_back(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        let actual_index = (self.head + self.len - 1 - index) % self.capacity;
+        self.buffer[actual_index].as_ref()
+    }
+
+    /// 只保留满足 `f` 的元素，保持原有顺序
+    ///
+    /// 依次从队头取出每个元素，满足条件的重新放回队尾，剩余元素在环中保持连续
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        for _ in 0..self.len {
+            let Some(value) = self.pop() else {
+                break;
+            };
+            if f(&value) {
+                // 刚取出一个元素，队列不会已满
+                let _ = self.push(value);
+            }
+        }
+    }
+
     /// 清空队列
     pub fn clear(&mut self) {
         while self.pop().is_some() {}
@@ -135,6 +170,16 @@ impl<T, const N: usize> CircularQueue<T, N> {
         Iter {
             queue: self,
             index: 0,
+            end: self.len,
+        }
+    }
+
+    /// 返回一个从队头到队尾的可变迭代器，可原地修改所有元素
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, N> {
+        // 队头之后的部分在前，绕回到缓冲区开头的部分在后
+        let (wrapped, front) = self.buffer.split_at_mut(self.head);
+        IterMut {
+            inner: front.iter_mut().chain(wrapped.iter_mut()).take(self.len),
         }
     }
 }
@@ -152,6 +197,30 @@ impl<T: Clone, const N: usize> CircularQueue<T, N> {
     }
 }
 
+impl<T: Into<f64> + Copy, const N: usize> CircularQueue<T, N> {
+    /// 队列中所有元素的平均值，队列为空时返回 `None`
+    #[allow(unused)]
+    pub fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let sum: f64 = self.iter().map(|&v| v.into()).sum();
+        Some(sum / self.len as f64)
+    }
+
+    /// 队列中所有元素的最小值，队列为空时返回 `None`
+    #[allow(unused)]
+    pub fn min(&self) -> Option<f64> {
+        self.iter().map(|&v| v.into()).reduce(f64::min)
+    }
+
+    /// 队列中所有元素的最大值，队列为空时返回 `None`
+    #[allow(unused)]
+    pub fn max(&self) -> Option<f64> {
+        self.iter().map(|&v| v.into()).reduce(f64::max)
+    }
+}
+
 impl<T, const N: usize> Default for CircularQueue<T, N>
 where
     T: Default,
@@ -163,18 +232,18 @@ where
 
 /// 循环队列的不可变迭代器
 ///
-/// 从队头到队尾顺序迭代，不会修改队列
+/// 从队头到队尾顺序迭代，不会修改队列；通过 `.rev()` 可从队尾向队头迭代
 pub struct Iter<'a, T, const N: usize> {
     queue: &'a CircularQueue<T, N>,
-    index: usize,
+    index: usize, // 下一个正向返回的逻辑位置
+    end: usize,   // 已反向返回部分的起始逻辑位置（不含）
 }
 
 impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let queue_len = self.queue.len();
-        if self.index >= queue_len {
+        if self.index >= self.end {
             return None;
         }
 
@@ -184,21 +253,98 @@ impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let queue_len = self.queue.len();
-        let remaining = queue_len.saturating_sub(self.index);
+        let remaining = self.end.saturating_sub(self.index);
         (remaining, Some(remaining))
     }
 }
 
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.index >= self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        let actual_index = (self.queue.head + self.end) % self.queue.capacity;
+        self.queue.buffer[actual_index].as_ref()
+    }
+}
+
 impl<'a, T, const N: usize> ExactSizeIterator for Iter<'a, T, N> {
     fn len(&self) -> usize {
-        let queue_len = self.queue.len();
-        queue_len.saturating_sub(self.index)
+        self.end.saturating_sub(self.index)
+    }
+}
+
+/// 循环队列的可变迭代器
+///
+/// 从队头到队尾顺序迭代，元素的顺序和数量不会改变
+pub struct IterMut<'a, T, const N: usize> {
+    inner: Take<SlotsMut<'a, T>>,
+}
+
+/// 队头之后的槽位与绕回部分的槽位依次连接
+type SlotsMut<'a, T> = Chain<slice::IterMut<'a, Option<T>>, slice::IterMut<'a, Option<T>>>;
+
+impl<'a, T, const N: usize> Iterator for IterMut<'a, T, N> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 队列范围内的槽位总是 Some
+        self.inner.next().and_then(Option::as_mut)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for IterMut<'a, T, N> {}
+
+/// 循环队列的所有权迭代器
+///
+/// 消耗队列，从队头到队尾依次返回元素
+pub struct IntoIter<T, const N: usize> {
+    queue: CircularQueue<T, N>,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len, Some(self.queue.len))
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> IntoIterator for CircularQueue<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { queue: self }
+    }
+}
+
+/// 依次 `push_overwrite`，元素多于 `N` 时只保留最新的 `N` 个
+impl<T, const N: usize> FromIterator<T> for CircularQueue<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut queue = Self::new();
+        for value in iter {
+            queue.push_overwrite(value);
+        }
+        queue
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
     fn test_basic_operations() {
@@ -318,6 +464,54 @@ mod tests {
         assert_eq!(values, vec![&3, &4, &5]);
     }
 
+    #[test]
+    fn test_reverse_iterator_after_wrap() {
+        let mut queue: CircularQueue<i32, 3> = CircularQueue::new();
+
+        // 填满并部分移除
+        queue.push(1).unwrap();
+        queue.push(2).unwrap();
+        queue.push(3).unwrap();
+        queue.pop();
+        queue.pop();
+
+        // 再添加（发生循环）
+        queue.push(4).unwrap();
+        queue.push(5).unwrap();
+
+        let values: Vec<&i32> = queue.iter().rev().collect();
+        assert_eq!(values, vec![&5, &4, &3]);
+
+        // 两端交替迭代时不会重复返回元素
+        let mut iter = queue.iter();
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut queue: CircularQueue<i32, 3> = CircularQueue::new();
+
+        queue.push(1).unwrap();
+        queue.push(2).unwrap();
+        queue.push(3).unwrap();
+        queue.pop();
+        queue.push(4).unwrap(); // 发生循环
+
+        assert_eq!(queue.iter_mut().len(), 3);
+        for value in queue.iter_mut() {
+            *value *= 2;
+        }
+
+        let values: Vec<&i32> = queue.iter().collect();
+        assert_eq!(values, vec![&4, &6, &8]);
+        assert_eq!(queue.pop(), Some(4));
+    }
+
     #[test]
     fn test_clear() {
         let mut queue: CircularQueue<i32, 4> = CircularQueue::new();
@@ -347,6 +541,102 @@ mod tests {
         assert_eq!(queue.get(3), None);
     }
 
+    #[test]
+    fn test_peek_back_and_get_back() {
+        let mut queue: CircularQueue<i32, 3> = CircularQueue::new();
+        assert_eq!(queue.peek_back(), None);
+        assert_eq!(queue.get_back(0), None);
+
+        queue.push(1).unwrap();
+        assert_eq!(queue.peek_back(), Some(&1));
+        assert_eq!(queue.peek_back(), queue.peek());
+        assert_eq!(queue.get_back(1), None);
+
+        // 覆盖后发生循环
+        queue.push_overwrite(2);
+        queue.push_overwrite(3);
+        queue.push_overwrite(4);
+        queue.push_overwrite(5);
+        assert_eq!(queue.peek_back(), Some(&5));
+        assert_eq!(queue.get_back(0), Some(&5));
+        assert_eq!(queue.get_back(1), Some(&4));
+        assert_eq!(queue.get_back(2), Some(&3));
+        assert_eq!(queue.get_back(3), None);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut queue: CircularQueue<String, 3> = CircularQueue::new();
+        for s in ["a", "b", "c", "d"] {
+            queue.push_overwrite(s.to_string());
+        }
+
+        // 绕回后仍按从旧到新的顺序返回所有权
+        let mut iter = queue.into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next().as_deref(), Some("b"));
+        assert_eq!(iter.collect::<Vec<_>>(), vec!["c", "d"]);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let queue: CircularQueue<i32, 5> = (1..=3).collect();
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        // 超过容量时只保留最新的 N 个
+        let queue: CircularQueue<i32, 3> = (1..=10).collect();
+        assert!(queue.is_full());
+        assert_eq!(queue.into_iter().collect::<Vec<_>>(), vec![8, 9, 10]);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut queue: CircularQueue<i32, 3> = CircularQueue::new();
+        assert_eq!(queue.mean(), None);
+        assert_eq!(queue.min(), None);
+        assert_eq!(queue.max(), None);
+
+        queue.push(4).unwrap();
+        queue.push(-2).unwrap();
+        assert_eq!(queue.mean(), Some(1.0));
+        assert_eq!(queue.min(), Some(-2.0));
+        assert_eq!(queue.max(), Some(4.0));
+
+        // 绕回后只统计队列中的元素：[10, 7, 1]
+        queue.push_overwrite(10);
+        queue.push_overwrite(7);
+        queue.push_overwrite(1);
+        assert_eq!(queue.mean(), Some(6.0));
+        assert_eq!(queue.min(), Some(1.0));
+        assert_eq!(queue.max(), Some(10.0));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut queue: CircularQueue<i32, 5> = CircularQueue::new();
+        for i in 1..=7 {
+            queue.push_overwrite(i);
+        }
+        queue.pop();
+        // 部分绕回：[4, 5, 6, 7]
+        queue.retain(|v| v % 2 == 0);
+        assert_eq!(queue.len(), 2);
+        assert!(!queue.is_full());
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![4, 6]);
+        assert_eq!(queue.peek_back(), Some(&6));
+
+        // 压缩后仍可正常写满
+        for i in 8..=10 {
+            queue.push(i).unwrap();
+        }
+        assert!(queue.is_full());
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![4, 6, 8, 9, 10]);
+
+        queue.retain(|_| false);
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+    }
+
     #[test]
     fn test_exact_size_iterator() {
         let mut queue: CircularQueue<i32, 5> = CircularQueue::new();
@@ -0,0 +1,121 @@
+//! 故障注入，用于在真机上验证各个恢复路径
+//!
+//! 仅在启用 `fault-inject` feature 的 debug 构建中编译。通过串口控制台输入命令
+//! 注入一次性故障，下一次经过对应位置时触发并自动清除：
+//! - `fault sensor`  - 下一次传感器读取返回 `TemperatureSensorError::Read`
+//! - `fault storage` - 下一条写入数据库的记录被替换为损坏的字节
+//! - `fault wifi`    - 下一次网关检测前断开 WiFi，由 `LivenessMonitor` 负责重连
+
+#[cfg(not(debug_assertions))]
+compile_error!("fault-inject 只能用于 debug 构建");
+
+use std::io::BufRead;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 可注入的故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    SensorRead,
+    StorageCorrupt,
+    WifiDrop,
+}
+
+impl Fault {
+    fn bit(self) -> u8 {
+        match self {
+            Fault::SensorRead => 1 << 0,
+            Fault::StorageCorrupt => 1 << 1,
+            Fault::WifiDrop => 1 << 2,
+        }
+    }
+}
+
+/// 损坏记录的内容：湿度字段为 0xFFFF，解析后远超 100%
+pub const CORRUPT_RECORD: [u8; 4] = [0xFF; 4];
+
+static PENDING: AtomicU8 = AtomicU8::new(0);
+
+/// 注入一次性故障
+pub fn inject(fault: Fault) {
+    PENDING.fetch_or(fault.bit(), Ordering::AcqRel);
+    log::warn!("已注入故障: {fault:?}");
+}
+
+/// 检查并清除故障，返回该故障是否处于待触发状态
+pub fn take(fault: Fault) -> bool {
+    let triggered = PENDING.fetch_and(!fault.bit(), Ordering::AcqRel) & fault.bit() != 0;
+    if triggered {
+        log::warn!("触发注入的故障: {fault:?}");
+    }
+    triggered
+}
+
+/// 解析控制台命令，例如 `fault sensor`
+pub fn parse_command(line: &str) -> Option<Fault> {
+    let mut parts = line.split_whitespace();
+    if parts.next()? != "fault" {
+        return None;
+    }
+    let fault = match parts.next()? {
+        "sensor" => Fault::SensorRead,
+        "storage" => Fault::StorageCorrupt,
+        "wifi" => Fault::WifiDrop,
+        _ => return None,
+    };
+    parts.next().is_none().then_some(fault)
+}
+
+/// 启动读取串口控制台命令的后台线程
+pub fn spawn_console() -> std::io::Result<()> {
+    std::thread::Builder::new()
+        .name("fault-console".into())
+        .stack_size(4096)
+        .spawn(|| {
+            log::warn!("故障注入控制台已启动，可用命令: fault sensor|storage|wifi");
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    continue;
+                };
+                match parse_command(&line) {
+                    Some(fault) => inject(fault),
+                    None if line.trim().is_empty() => {}
+                    None => log::warn!("无法识别的故障注入命令: {}", line.trim()),
+                }
+            }
+        })
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::info_def::InfoSlot;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("fault sensor"), Some(Fault::SensorRead));
+        assert_eq!(parse_command("  fault   storage \r"), Some(Fault::StorageCorrupt));
+        assert_eq!(parse_command("fault wifi"), Some(Fault::WifiDrop));
+
+        assert_eq!(parse_command("fault"), None);
+        assert_eq!(parse_command("fault disk"), None);
+        assert_eq!(parse_command("fault wifi now"), None);
+        assert_eq!(parse_command("sensor"), None);
+    }
+
+    #[test]
+    fn test_inject_is_one_shot() {
+        inject(Fault::WifiDrop);
+        assert!(!take(Fault::SensorRead));
+        assert!(take(Fault::WifiDrop));
+        // 触发后自动清除
+        assert!(!take(Fault::WifiDrop));
+    }
+
+    #[test]
+    fn test_corrupt_record_is_implausible() {
+        // 损坏的记录读回后湿度超出 0~100%，会被当作异常数据处理
+        let slot = InfoSlot::from_bytes(&CORRUPT_RECORD);
+        assert!(slot.get_humidity() > 100.0);
+    }
+}
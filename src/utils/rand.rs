@@ -1,51 +1,108 @@
-// use rand_pcg::Pcg64;  // 或 rand_chacha::ChaCha20Rng
-// use rand_core::{RngCore, SeedableRng};
-// use std::time::UNIX_EPOCH;
-
-// pub struct RandomGenerator {
-//     rng: Pcg64,
-// }
-
-// impl RandomGenerator {
-//     pub fn new() -> Self {
-//         let rng = init_rng();
-//         RandomGenerator { rng }
-//     }
-
-//     pub fn next_u32(&mut self) -> u32 {
-//         self.rng.next_u32()
-//     }
-
-//     // pub fn next_u64(&mut self) -> u64 {
-//     //     self.rng.next_u64()
-//     // }
-
-//     // pub fn fill_bytes(&mut self, dest: &mut [u8]) {
-//     //     self.rng.fill_bytes(dest);
-//     // }
-// }
-
-// // 初始化 RNG（使用固定种子，或从硬件获取）
-// pub fn init_rng() -> Pcg64 {
-//     // 示例：使用固定种子（生产中替换为动态种子，如 RTC 时间）
-//     let time = std::time::SystemTime::now()
-//         .duration_since(UNIX_EPOCH)
-//         .unwrap()
-//         .as_secs();
-//     let seed = [time as u8; 32];  // 32 字节种子
-//     Pcg64::from_seed(seed)
-// }
-
-// // // 生成随机数
-// // pub fn generate_random_u32(rng: &mut Pcg64) -> u32 {
-// //     rng.next_u32()
-// // }
-
-// // pub fn generate_random_u64(rng: &mut Pcg64) -> u64 {
-// //     rng.next_u64()
-// // }
-
-// // // 示例：生成随机字节数组
-// // pub fn generate_random_bytes(rng: &mut Pcg64, dest: &mut [u8]) {
-// //     rng.fill_bytes(dest);
-// // }
\ No newline at end of file
+//! 随机数生成
+//!
+//! 使用 ESP32 硬件随机数发生器（`esp_fill_random`）生成种子，再由 PCG64 生成随机数。
+//! `get_info_slot` 生成模拟的温湿度读数，没有连接传感器时也可以调试屏幕和数据库。
+//!
+//! 硬件随机数在 WiFi/蓝牙启用时为真随机，否则熵源较弱，仅用于模拟数据已经足够。
+
+use crate::data::info_def::InfoSlot;
+use rand_core::{RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+
+/// 模拟温度范围（°C）
+const SIM_TEMPERATURE: (f32, f32) = (18.0, 30.0);
+/// 模拟湿度范围（%）
+const SIM_HUMIDITY: (f32, f32) = (30.0, 70.0);
+/// 相邻两次模拟读数的最大变化量（温度 °C，湿度 %）
+const SIM_STEP: (f32, f32) = (0.3, 1.0);
+
+pub struct RandomGenerator {
+    rng: Pcg64,
+    /// 上一次模拟的温湿度，模拟读数在此基础上随机游走
+    last_reading: Option<(f32, f32)>,
+}
+
+impl RandomGenerator {
+    /// 使用硬件随机数作为种子创建生成器
+    pub fn new() -> Self {
+        let mut seed = <Pcg64 as SeedableRng>::Seed::default();
+        unsafe { esp_idf_sys::esp_fill_random(seed.as_mut_ptr().cast(), seed.len()) };
+        Self::from_rng(Pcg64::from_seed(seed))
+    }
+
+    /// 使用固定种子创建生成器，相同种子产生相同的序列
+    pub fn from_seed(seed: u64) -> Self {
+        Self::from_rng(Pcg64::seed_from_u64(seed))
+    }
+
+    fn from_rng(rng: Pcg64) -> Self {
+        Self {
+            rng,
+            last_reading: None,
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    /// 返回 `[lo, hi]` 内均匀分布的随机数，要求 `lo <= hi`
+    pub fn next_f32_range(&mut self, lo: f32, hi: f32) -> f32 {
+        // 取高 24 位，f32 的尾数可以精确表示 [0, 1) 内的值
+        let unit = (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32;
+        // 浮点舍入可能略微越界，限制在范围内
+        (lo + (hi - lo) * unit).clamp(lo, hi)
+    }
+
+    /// 生成一条模拟的温湿度读数
+    ///
+    /// 第一次调用时在模拟范围内随机取值，之后每次在上一次读数附近小幅变化，
+    /// 曲线与真实传感器相近，便于调试屏幕曲线和数据库
+    pub fn get_info_slot(&mut self) -> InfoSlot {
+        let (temperature, humidity) = match self.last_reading {
+            None => (
+                self.next_f32_range(SIM_TEMPERATURE.0, SIM_TEMPERATURE.1),
+                self.next_f32_range(SIM_HUMIDITY.0, SIM_HUMIDITY.1),
+            ),
+            Some((t, h)) => (
+                (t + self.next_f32_range(-SIM_STEP.0, SIM_STEP.0))
+                    .clamp(SIM_TEMPERATURE.0, SIM_TEMPERATURE.1),
+                (h + self.next_f32_range(-SIM_STEP.1, SIM_STEP.1))
+                    .clamp(SIM_HUMIDITY.0, SIM_HUMIDITY.1),
+            ),
+        };
+        self.last_reading = Some((temperature, humidity));
+        InfoSlot::new_from_f32(temperature, humidity)
+    }
+}
+
+impl Default for RandomGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_f32_range_in_bounds() {
+        let mut rng = RandomGenerator::from_seed(42);
+        for _ in 0..10_000 {
+            let v = rng.next_f32_range(-1.5, 2.5);
+            assert!((-1.5..=2.5).contains(&v), "{v} 超出范围");
+        }
+        assert_eq!(rng.next_f32_range(3.0, 3.0), 3.0);
+    }
+
+    #[test]
+    fn test_info_slot_plausible() {
+        let mut rng = RandomGenerator::from_seed(7);
+        for _ in 0..1000 {
+            let slot = rng.get_info_slot();
+            assert!((SIM_TEMPERATURE.0..=SIM_TEMPERATURE.1).contains(&slot.get_temperature()));
+            assert!((SIM_HUMIDITY.0..=SIM_HUMIDITY.1).contains(&slot.get_humidity()));
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+
+/// 按字符数截断字符串，保证不会在多字节字符中间截断
+///
+/// # 参数
+/// - `s`: 原始字符串
+/// - `max`: 最多保留的字符数
+pub fn truncate_chars(s: &str, max: usize) -> &str {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+/// 按字符数截断字符串，发生截断时以 "…" 结尾（计入 `max`）
+///
+/// 未超出长度时不分配内存，直接借用原字符串
+pub fn truncate_with_ellipsis(s: &str, max: usize) -> Cow<'_, str> {
+    if s.chars().nth(max).is_none() {
+        return Cow::Borrowed(s);
+    }
+    if max == 0 {
+        return Cow::Borrowed("");
+    }
+    let mut out = truncate_chars(s, max - 1).to_string();
+    out.push('…');
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("hello", 10), "hello");
+        assert_eq!(truncate_chars("hello", 5), "hello");
+        assert_eq!(truncate_chars("hello", 3), "hel");
+        assert_eq!(truncate_chars("hello", 0), "");
+
+        // 多字节字符：按字符而不是字节截断
+        assert_eq!(truncate_chars("温度传感器", 2), "温度");
+        assert_eq!(truncate_chars("a温b", 2), "a温");
+        assert_eq!(truncate_chars("🌡️25°C", 1), "🌡");
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis() {
+        assert!(matches!(truncate_with_ellipsis("温度", 2), Cow::Borrowed("温度")));
+        assert_eq!(truncate_with_ellipsis("温度传感器", 3), "温度…");
+        assert_eq!(truncate_with_ellipsis("温度传感器", 1), "…");
+        assert_eq!(truncate_with_ellipsis("温度传感器", 0), "");
+        assert_eq!(truncate_with_ellipsis("abcdef", 4).chars().count(), 4);
+    }
+}
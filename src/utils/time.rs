@@ -1,3 +1,4 @@
+use thiserror::Error;
 use time::{format_description, OffsetDateTime, UtcOffset};
 
 /// 获取当前 unix 时间戳（秒）
@@ -8,11 +9,21 @@ pub fn get_unix_timestamp() -> Option<i64> {
         .map(|d| d.as_secs() as i64)
 }
 
+/// 获取当前 unix 时间戳（毫秒），同一秒内的多次读数也能得到不同的时间戳
+pub fn get_unix_timestamp_millis() -> Option<i64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .and_then(|d| i64::try_from(d.as_millis()).ok())
+}
+
 /// 获取格式化的当前时间字符串（带时区）
 /// 
 /// # 参数
 /// - `format_str`: 时间格式字符串（如 "[year]-[month]-[day] [hour]:[minute]:[second]"）
 /// - `offset_secs`: 时区偏移（秒），如东八区为 8*3600
+///
+/// 偏移或格式无效时返回 `None`，新代码请使用带错误信息的 `format_now`
 pub fn get_formatted_time(format_str: &str, offset_secs: i32) -> Option<String> {
     let timestamp = get_unix_timestamp()?;
     let offset = UtcOffset::from_whole_seconds(offset_secs).ok()?;
@@ -20,3 +31,236 @@ pub fn get_formatted_time(format_str: &str, offset_secs: i32) -> Option<String>
     let format = format_description::parse(format_str).ok()?;
     datetime.format(&format).ok()
 }
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TimeFormatError {
+    #[error("时区偏移 {0} 秒超出 ±14 小时范围")]
+    OffsetOutOfRange(i32),
+    #[error("时间格式字符串无效: {0}")]
+    InvalidFormat(String),
+    #[error("系统时间无效")]
+    InvalidTime,
+}
+
+/// 固定偏移的时区（不处理夏令时），偏移范围为 ±14 小时
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timezone {
+    offset_secs: i32,
+}
+
+impl Timezone {
+    /// 允许的最大偏移（小时），对应 UTC+14（基里巴斯）
+    pub const MAX_OFFSET_HOURS: i8 = 14;
+
+    pub const fn utc() -> Self {
+        Self { offset_secs: 0 }
+    }
+
+    /// 中国标准时间 UTC+8
+    pub const fn china() -> Self {
+        Self {
+            offset_secs: 8 * 3600,
+        }
+    }
+
+    /// 整小时偏移的时区，如 `Timezone::from_hours(-5)` 为 UTC-5
+    pub fn from_hours(hours: i8) -> Result<Self, TimeFormatError> {
+        Self::from_offset_secs(hours as i32 * 3600)
+    }
+
+    /// 按秒指定偏移，用于 UTC+5:30 等非整小时时区
+    pub fn from_offset_secs(offset_secs: i32) -> Result<Self, TimeFormatError> {
+        if offset_secs.unsigned_abs() > Self::MAX_OFFSET_HOURS as u32 * 3600 {
+            return Err(TimeFormatError::OffsetOutOfRange(offset_secs));
+        }
+        Ok(Self { offset_secs })
+    }
+
+    /// 相对 UTC 的偏移（秒）
+    pub fn offset_secs(self) -> i32 {
+        self.offset_secs
+    }
+}
+
+/// 按时区格式化当前时间
+///
+/// # 参数
+/// - `tz`: 时区
+/// - `format_str`: 时间格式字符串（如 "[year]-[month]-[day] [hour]:[minute]:[second]"）
+pub fn format_now(tz: Timezone, format_str: &str) -> Result<String, TimeFormatError> {
+    let timestamp = get_unix_timestamp().ok_or(TimeFormatError::InvalidTime)?;
+    format_unix(timestamp, tz, format_str)
+}
+
+/// 按时区格式化 unix 时间戳（秒）
+pub fn format_unix(timestamp: i64, tz: Timezone, format_str: &str) -> Result<String, TimeFormatError> {
+    // 构造时已校验偏移范围，UtcOffset 支持 ±25 小时
+    let offset = UtcOffset::from_whole_seconds(tz.offset_secs)
+        .map_err(|_| TimeFormatError::OffsetOutOfRange(tz.offset_secs))?;
+    let datetime = OffsetDateTime::from_unix_timestamp(timestamp)
+        .map_err(|_| TimeFormatError::InvalidTime)?
+        .to_offset(offset);
+    let format = format_description::parse(format_str)
+        .map_err(|e| TimeFormatError::InvalidFormat(e.to_string()))?;
+    datetime
+        .format(&format)
+        .map_err(|e| TimeFormatError::InvalidFormat(e.to_string()))
+}
+
+/// 自启动（含深度睡眠唤醒后的重新启动）以来经过的秒数
+pub fn uptime_secs() -> u64 {
+    // esp_timer 从启动时开始计时，单位为微秒
+    let micros = unsafe { esp_idf_sys::esp_timer_get_time() };
+    micros.max(0) as u64 / 1_000_000
+}
+
+/// 将秒数格式化为 `Nd HH:MM:SS`，不足一天时省略天数部分，如 `3d 04:12:07`、`00:05:30`
+pub fn format_duration(secs: u64) -> String {
+    let days = secs / 86_400;
+    let hours = secs % 86_400 / 3600;
+    let minutes = secs % 3600 / 60;
+    let seconds = secs % 60;
+    if days > 0 {
+        format!("{days}d {hours:02}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{hours:02}:{minutes:02}:{seconds:02}")
+    }
+}
+
+/// 导出时间戳的格式，只影响导出/接口输出，存储中的时间戳单位见 `TimeUnit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampMode {
+    /// unix 时间戳（秒）
+    #[default]
+    UnixSeconds,
+    /// unix 时间戳（毫秒）
+    UnixMillis,
+    /// 相对自定义纪元（unix 秒）的秒数，早于纪元时为负数
+    SinceEpoch(i64),
+}
+
+/// 将 unix 时间戳（秒）转换为指定格式，毫秒时间戳使用 `format_timestamp_in`
+pub fn format_timestamp(ts: i64, mode: TimestampMode) -> i64 {
+    match mode {
+        TimestampMode::UnixSeconds => ts,
+        TimestampMode::UnixMillis => ts.saturating_mul(1000),
+        TimestampMode::SinceEpoch(epoch) => ts.saturating_sub(epoch),
+    }
+}
+
+/// 存储中时间戳的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeUnit {
+    /// unix 时间戳（秒），`get_unix_timestamp` 的返回值
+    #[default]
+    Seconds,
+    /// unix 时间戳（毫秒），`get_unix_timestamp_millis` 的返回值
+    Millis,
+}
+
+impl TimeUnit {
+    /// 每秒的时间戳数
+    pub fn ticks_per_sec(self) -> i64 {
+        match self {
+            TimeUnit::Seconds => 1,
+            TimeUnit::Millis => 1000,
+        }
+    }
+}
+
+/// 将以 `unit` 为单位的存储时间戳转换为指定格式
+///
+/// 毫秒时间戳按 `UnixMillis` 导出时保留毫秒精度，其余格式向下取整到秒
+pub fn format_timestamp_in(ts: i64, unit: TimeUnit, mode: TimestampMode) -> i64 {
+    match (unit, mode) {
+        (TimeUnit::Millis, TimestampMode::UnixMillis) => ts,
+        (TimeUnit::Millis, _) => format_timestamp(ts.div_euclid(1000), mode),
+        (TimeUnit::Seconds, _) => format_timestamp(ts, mode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        // 2024-01-01 00:00:00 UTC
+        let ts = 1_704_067_200;
+        assert_eq!(format_timestamp(ts, TimestampMode::default()), ts);
+        assert_eq!(format_timestamp(ts, TimestampMode::UnixMillis), 1_704_067_200_000);
+
+        // 相对 2000-01-01 00:00:00 UTC
+        let y2k = 946_684_800;
+        assert_eq!(format_timestamp(ts, TimestampMode::SinceEpoch(y2k)), 757_382_400);
+        assert_eq!(format_timestamp(y2k, TimestampMode::SinceEpoch(ts)), -757_382_400);
+    }
+
+    #[test]
+    fn test_format_timestamp_in_millis() {
+        let ms = 1_704_067_200_250;
+        assert_eq!(format_timestamp_in(ms, TimeUnit::Millis, TimestampMode::UnixMillis), ms);
+        assert_eq!(
+            format_timestamp_in(ms, TimeUnit::Millis, TimestampMode::UnixSeconds),
+            1_704_067_200
+        );
+        assert_eq!(
+            format_timestamp_in(1_704_067_200, TimeUnit::Seconds, TimestampMode::UnixMillis),
+            1_704_067_200_000
+        );
+    }
+
+    #[test]
+    fn test_timestamp_millis_distinct() {
+        let first = get_unix_timestamp_millis().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        let second = get_unix_timestamp_millis().unwrap();
+        assert!(second - first >= 200);
+        // 秒级时间戳在同一秒内会重复，毫秒时间戳不会
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "00:00:00");
+        assert_eq!(format_duration(59), "00:00:59");
+        assert_eq!(format_duration(60), "00:01:00");
+        assert_eq!(format_duration(3599), "00:59:59");
+        assert_eq!(format_duration(3600), "01:00:00");
+        assert_eq!(format_duration(86_399), "23:59:59");
+        assert_eq!(format_duration(86_400), "1d 00:00:00");
+        assert_eq!(format_duration(3 * 86_400 + 4 * 3600 + 12 * 60 + 7), "3d 04:12:07");
+    }
+
+    #[test]
+    fn test_timezone() {
+        assert_eq!(Timezone::from_hours(8), Ok(Timezone::china()));
+        assert_eq!(Timezone::from_hours(0), Ok(Timezone::utc()));
+        assert_eq!(Timezone::from_hours(-14).unwrap().offset_secs(), -14 * 3600);
+        assert_eq!(
+            Timezone::from_hours(30),
+            Err(TimeFormatError::OffsetOutOfRange(30 * 3600))
+        );
+        assert_eq!(
+            Timezone::from_offset_secs(-(14 * 3600 + 1)),
+            Err(TimeFormatError::OffsetOutOfRange(-(14 * 3600 + 1)))
+        );
+    }
+
+    #[test]
+    fn test_format_unix() {
+        let fmt = "[year]-[month]-[day] [hour]:[minute]:[second]";
+        // 2024-01-01 00:00:00 UTC
+        let ts = 1_704_067_200;
+        assert_eq!(format_unix(ts, Timezone::utc(), fmt).unwrap(), "2024-01-01 00:00:00");
+        assert_eq!(format_unix(ts, Timezone::china(), fmt).unwrap(), "2024-01-01 08:00:00");
+        assert_eq!(
+            format_unix(ts, Timezone::from_hours(-5).unwrap(), fmt).unwrap(),
+            "2023-12-31 19:00:00"
+        );
+        assert!(matches!(
+            format_unix(ts, Timezone::utc(), "[invalid]"),
+            Err(TimeFormatError::InvalidFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+//! 任务看门狗（Task WDT）
+//!
+//! ESP-IDF 默认启用任务看门狗，相关配置项:
+//! - `CONFIG_ESP_TASK_WDT_EN=y` / `CONFIG_ESP_TASK_WDT_INIT=y`: 启用并在启动时初始化看门狗
+//! - `CONFIG_ESP_TASK_WDT_TIMEOUT_S`: 超时时间，默认 5 秒
+//! - `CONFIG_ESP_TASK_WDT_PANIC=y`: 超时后触发 panic 并重启芯片，否则只打印警告
+//!
+//! 订阅了看门狗的任务必须在超时前喂狗。NTP 同步和 WiFi 连接的等待循环会在每次迭代中调用 `feed`，
+//! 调用方用 `TaskWdt::subscribe` 订阅当前任务后，这些长时间等待不会被误判为卡死；
+//! 当前任务未订阅时 `feed` 不做任何事。
+//!
+//! `wifi.scan()`、`wait_netif_up()` 等单次阻塞调用期间无法喂狗，
+//! 订阅主任务时请将超时时间设置得比这些调用更长（如 `CONFIG_ESP_TASK_WDT_TIMEOUT_S=20`）。
+
+use esp_idf_sys::{esp, esp_task_wdt_add, esp_task_wdt_delete, esp_task_wdt_reset, EspError};
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+thread_local! {
+    /// 当前任务是否通过 `TaskWdt` 订阅了看门狗
+    static SUBSCRIBED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 喂狗，当前任务未订阅看门狗时直接返回
+///
+/// 未订阅时调用 `esp_task_wdt_reset` 会打印错误日志，因此先检查订阅状态
+pub fn feed() {
+    if SUBSCRIBED.with(Cell::get) {
+        unsafe { esp_task_wdt_reset() };
+    }
+}
+
+/// 当前任务的看门狗订阅，drop 时取消订阅
+///
+/// 只能在订阅的任务中使用，不能发送到其他线程
+pub struct TaskWdt {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl TaskWdt {
+    /// 将当前任务加入看门狗监视
+    ///
+    /// 看门狗未初始化（`CONFIG_ESP_TASK_WDT_INIT=n`）或当前任务已订阅时返回错误
+    pub fn subscribe() -> Result<Self, EspError> {
+        esp!(unsafe { esp_task_wdt_add(core::ptr::null_mut()) })?;
+        SUBSCRIBED.with(|s| s.set(true));
+        log::info!("当前任务已订阅任务看门狗");
+        Ok(Self {
+            _not_send: PhantomData,
+        })
+    }
+
+    /// 喂狗
+    pub fn feed(&self) {
+        feed();
+    }
+}
+
+impl Drop for TaskWdt {
+    fn drop(&mut self) {
+        SUBSCRIBED.with(|s| s.set(false));
+        if let Err(e) = esp!(unsafe { esp_task_wdt_delete(core::ptr::null_mut()) }) {
+            log::warn!("取消订阅任务看门狗失败: {e}");
+        }
+    }
+}